@@ -0,0 +1,23 @@
+export interface Submission {
+  nickname?: string;
+  age: number;
+  volume: number;
+  agrees: boolean;
+  passport: unknown;
+  photo: unknown;
+  colour: "red" | "dark \"blue\"";
+  toppings: string[];
+  comments: string;
+  "birth-date": string;
+  opens: string;
+  appointment: string;
+  "start-month": string;
+  "holiday-week": string;
+  email?: string;
+  phone?: string;
+  homepage?: string;
+  secret: string;
+  "favourite-colour": string;
+  schedule: unknown;
+  record_id: string;
+}
@@ -0,0 +1,46 @@
+// date picker widget shared by the residence forms
+(function () {
+  'use strict';
+  var months = ['January', 'February', 'March', 'April', 'May', 'June', 'July', 'August', 'September', 'October', 'November', 'December'];
+  function cell0(day) { var el = document.createElement('td'); el.textContent = String(day + 0); return el; }
+  function cell1(day) { var el = document.createElement('td'); el.textContent = String(day + 1); return el; }
+  function cell2(day) { var el = document.createElement('td'); el.textContent = String(day + 2); return el; }
+  function cell3(day) { var el = document.createElement('td'); el.textContent = String(day + 3); return el; }
+  function cell4(day) { var el = document.createElement('td'); el.textContent = String(day + 4); return el; }
+  function cell5(day) { var el = document.createElement('td'); el.textContent = String(day + 5); return el; }
+  function cell6(day) { var el = document.createElement('td'); el.textContent = String(day + 6); return el; }
+  function cell7(day) { var el = document.createElement('td'); el.textContent = String(day + 7); return el; }
+  function cell8(day) { var el = document.createElement('td'); el.textContent = String(day + 8); return el; }
+  function cell9(day) { var el = document.createElement('td'); el.textContent = String(day + 9); return el; }
+  function cell10(day) { var el = document.createElement('td'); el.textContent = String(day + 10); return el; }
+  function cell11(day) { var el = document.createElement('td'); el.textContent = String(day + 11); return el; }
+  function cell12(day) { var el = document.createElement('td'); el.textContent = String(day + 12); return el; }
+  function cell13(day) { var el = document.createElement('td'); el.textContent = String(day + 13); return el; }
+  function cell14(day) { var el = document.createElement('td'); el.textContent = String(day + 14); return el; }
+  function cell15(day) { var el = document.createElement('td'); el.textContent = String(day + 15); return el; }
+  function cell16(day) { var el = document.createElement('td'); el.textContent = String(day + 16); return el; }
+  function cell17(day) { var el = document.createElement('td'); el.textContent = String(day + 17); return el; }
+  function cell18(day) { var el = document.createElement('td'); el.textContent = String(day + 18); return el; }
+  function cell19(day) { var el = document.createElement('td'); el.textContent = String(day + 19); return el; }
+  function cell20(day) { var el = document.createElement('td'); el.textContent = String(day + 20); return el; }
+  function cell21(day) { var el = document.createElement('td'); el.textContent = String(day + 21); return el; }
+  function cell22(day) { var el = document.createElement('td'); el.textContent = String(day + 22); return el; }
+  function cell23(day) { var el = document.createElement('td'); el.textContent = String(day + 23); return el; }
+  function cell24(day) { var el = document.createElement('td'); el.textContent = String(day + 24); return el; }
+  function cell25(day) { var el = document.createElement('td'); el.textContent = String(day + 25); return el; }
+  function cell26(day) { var el = document.createElement('td'); el.textContent = String(day + 26); return el; }
+  function cell27(day) { var el = document.createElement('td'); el.textContent = String(day + 27); return el; }
+  function cell28(day) { var el = document.createElement('td'); el.textContent = String(day + 28); return el; }
+  function cell29(day) { var el = document.createElement('td'); el.textContent = String(day + 29); return el; }
+  function cell30(day) { var el = document.createElement('td'); el.textContent = String(day + 30); return el; }
+  function cell31(day) { var el = document.createElement('td'); el.textContent = String(day + 31); return el; }
+  function cell32(day) { var el = document.createElement('td'); el.textContent = String(day + 32); return el; }
+  function cell33(day) { var el = document.createElement('td'); el.textContent = String(day + 33); return el; }
+  function cell34(day) { var el = document.createElement('td'); el.textContent = String(day + 34); return el; }
+  function cell35(day) { var el = document.createElement('td'); el.textContent = String(day + 35); return el; }
+  function cell36(day) { var el = document.createElement('td'); el.textContent = String(day + 36); return el; }
+  function cell37(day) { var el = document.createElement('td'); el.textContent = String(day + 37); return el; }
+  function cell38(day) { var el = document.createElement('td'); el.textContent = String(day + 38); return el; }
+  function cell39(day) { var el = document.createElement('td'); el.textContent = String(day + 39); return el; }
+  window.mouseFormsDatePicker = { months: months };
+})();
@@ -0,0 +1,57 @@
+use super::*;
+use sha2::{Digest, Sha256};
+use std::fs;
+
+/// Where and when to extract embedded scripts and stylesheets.
+///
+/// Assets larger than `threshold` bytes are written to `output_dir` as
+/// `<sha256>.js` or `<sha256>.css` and replaced in the form by a reference
+/// holding the path, hash and size of the file. Since files are named by
+/// their content, forms sharing a script also share the extracted file.
+#[derive(Debug, Clone)]
+pub struct ExtractPolicy {
+    threshold: usize,
+    output_dir: PathBuf,
+}
+
+impl ExtractPolicy {
+    pub fn new(threshold: usize, output_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            threshold,
+            output_dir: output_dir.into(),
+        }
+    }
+
+    fn extract(&self, asset: &mut Asset, extension: &str) -> io::Result<()> {
+        let content = match asset {
+            Asset::Inline(content) if content.len() > self.threshold => content,
+            _ => return Ok(()),
+        };
+
+        let sha256 = format!("{:x}", Sha256::digest(content.as_bytes()));
+        let path = self.output_dir.join(format!("{}.{}", sha256, extension));
+        if !path.exists() {
+            fs::create_dir_all(&self.output_dir)?;
+            fs::write(&path, content.as_bytes())?;
+        }
+
+        *asset = Asset::Extracted(ExtractedAsset {
+            path: path.to_string_lossy().into_owned(),
+            sha256,
+            size: content.len(),
+        });
+        Ok(())
+    }
+}
+
+impl Form {
+    pub(crate) fn extract_assets(&mut self, policy: &ExtractPolicy) -> io::Result<()> {
+        for script in &mut self.embedded_scripts {
+            policy.extract(&mut script.asset, "js")?;
+        }
+        for stylesheet in &mut self.stylesheets {
+            policy.extract(stylesheet, "css")?;
+        }
+        Ok(())
+    }
+}
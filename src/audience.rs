@@ -0,0 +1,87 @@
+//! Variants of a form for different audiences.
+//!
+//! Sections, groups, fields and options can be limited to some audiences with
+//! `audience="staff reviewers"`, e.g. for case numbers and notes that must
+//! only ever appear in an internal version of a form. Elements without an
+//! `audience` are part of every variant.
+
+use super::*;
+
+impl ElementAttributes {
+    fn is_for(&self, audience: &str) -> bool {
+        self.audience.is_empty() || self.audience.iter().any(|name| name == audience)
+    }
+}
+
+impl FormField {
+    fn into_audience(mut self, audience: &str) -> Option<Self> {
+        if !self.attributes.is_for(audience) {
+            return None;
+        }
+        self.options
+            .retain(|option| option.attributes.is_for(audience));
+        Some(self)
+    }
+}
+
+impl FormGroup {
+    /// `None` if the group is not for `audience`, or none of its members are.
+    fn into_audience(mut self, audience: &str) -> Option<Self> {
+        if !self.attributes.is_for(audience) {
+            return None;
+        }
+        let members = std::mem::take(&mut self.members);
+        let had_members = !members.is_empty();
+        self.members = members
+            .into_iter()
+            .filter_map(|field| field.into_audience(audience))
+            .collect();
+        if had_members && self.members.is_empty() {
+            None
+        } else {
+            Some(self)
+        }
+    }
+}
+
+impl FormSection {
+    /// `None` if the section is not for `audience`, or none of its elements
+    /// are.
+    fn into_audience(mut self, audience: &str) -> Option<Self> {
+        if !self.attributes.is_for(audience) {
+            return None;
+        }
+        let elements = std::mem::take(&mut self.elements);
+        let had_elements = !elements.is_empty();
+        self.elements = elements
+            .into_iter()
+            .filter_map(|element| match element {
+                FormElement::Field(field) => field.into_audience(audience).map(FormElement::Field),
+                FormElement::Group(group) => group.into_audience(audience).map(FormElement::Group),
+            })
+            .collect();
+        if had_elements && self.elements.is_empty() {
+            None
+        } else {
+            Some(self)
+        }
+    }
+}
+
+impl Form {
+    /// A copy of the form with only the elements meant for `audience`.
+    ///
+    /// Groups and sections left empty by this are removed as well.
+    /// Conditions are not checked here; a condition on a field that this
+    /// variant leaves out is reported by [`Form::validate`] on the result, or
+    /// when compiling with [`CompileOptions::audience`].
+    pub fn for_audience(&self, audience: &str) -> Form {
+        let mut form = self.clone();
+        let sections = std::mem::take(&mut form.sections);
+        form.sections = sections
+            .into_iter()
+            .filter_map(|section| section.into_audience(audience))
+            .collect();
+        form
+    }
+}
@@ -0,0 +1,814 @@
+//! Building forms in code rather than from pug.
+//!
+//! The builders collect the same attributes a template would and hand them to
+//! the parser's own constructors, so a built form is validated exactly like a
+//! compiled one.
+//!
+//! ```
+//! use mouse_forms::{FieldBuilder, FieldType, FormBuilder, SectionBuilder};
+//!
+//! let form = FormBuilder::new()
+//!     .title("Contact")
+//!     .language("en")
+//!     .section(
+//!         SectionBuilder::new("contact")
+//!             .title("How can we reach you?")
+//!             .field(FieldBuilder::new("email", FieldType::Email).label("Email"))
+//!             .field(
+//!                 FieldBuilder::new("phone", FieldType::Tel)
+//!                     .label("Phone")
+//!                     .optional(),
+//!             ),
+//!     )
+//!     .build()
+//!     .unwrap();
+//! ```
+
+use super::*;
+use xml::{attribute::OwnedAttribute, name::OwnedName};
+
+fn attribute(name: &str, value: impl Into<String>) -> OwnedAttribute {
+    OwnedAttribute::new(OwnedName::local(name), value)
+}
+
+fn finish<T>(element: Option<T>, errors: Vec<SyntacticError>) -> Result<T, SyntacticErrors> {
+    match element {
+        Some(element) if errors.is_empty() => Ok(element),
+        _ => Err(SyntacticErrors::from(errors)),
+    }
+}
+
+pub struct FormBuilder {
+    form: Form,
+    sections: Vec<SectionBuilder>,
+}
+
+impl FormBuilder {
+    pub fn new() -> Self {
+        Self {
+            form: Form::new(),
+            sections: Vec::new(),
+        }
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.form.title = Some(title.into());
+        self
+    }
+
+    /// Sets the description, which also serves as the meta and directory
+    /// descriptions unless those are set as well.
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.form.description = Some(description.into());
+        self
+    }
+
+    pub fn meta_description(mut self, meta_description: impl Into<String>) -> Self {
+        self.form.meta_description = Some(meta_description.into());
+        self
+    }
+
+    pub fn dir_description(mut self, dir_description: impl Into<String>) -> Self {
+        self.form.dir_description = Some(dir_description.into());
+        self
+    }
+
+    pub fn instructions(mut self, instructions: impl Into<String>) -> Self {
+        self.form.instructions = Some(instructions.into());
+        self
+    }
+
+    pub fn language(mut self, language: impl Into<String>) -> Self {
+        self.form.language = Some(language.into());
+        self
+    }
+
+    /// Adds comma or line separated keywords; like `keywords` elements,
+    /// calling this again adds to the keywords rather than replacing them.
+    pub fn keywords(mut self, keywords: impl Into<String>) -> Self {
+        self.form.add_keywords(&keywords.into());
+        self
+    }
+
+    pub fn category(mut self, category: impl Into<String>) -> Self {
+        self.form.category = Some(category.into());
+        self
+    }
+
+    /// Adds a related link; the first one is also the form's `link`.
+    pub fn link(mut self, href: impl Into<String>) -> Self {
+        self.form.add_link(FormLink {
+            href: href.into(),
+            label: None,
+        });
+        self
+    }
+
+    pub fn labeled_link(mut self, href: impl Into<String>, label: impl Into<String>) -> Self {
+        self.form.add_link(FormLink {
+            href: href.into(),
+            label: Some(label.into()),
+        });
+        self
+    }
+
+    /// Posts the form to `action` with `method`; `label` is the text of the
+    /// submit button.
+    pub fn submit(
+        mut self,
+        action: impl Into<String>,
+        method: SubmitMethod,
+        label: Option<String>,
+    ) -> Self {
+        self.form.submit = Some(SubmitTarget {
+            action: Some(action.into()),
+            method,
+            label,
+        });
+        self
+    }
+
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.form.version = Some(version.into());
+        self
+    }
+
+    pub fn author(mut self, author: impl Into<String>) -> Self {
+        self.form.author = Some(author.into());
+        self
+    }
+
+    /// Sets when the form was last changed, an ISO 8601 date like
+    /// `2024-03-01`, which [`build`](Self::build) checks.
+    pub fn updated(mut self, updated: impl Into<String>) -> Self {
+        self.form.updated = Some(updated.into());
+        self
+    }
+
+    pub fn index(mut self, index: u32) -> Self {
+        self.form.index = Some(index);
+        self
+    }
+
+    pub fn unlisted(mut self) -> Self {
+        self.form.unlisted = true;
+        self
+    }
+
+    /// Fields are optional unless they are marked `required`.
+    pub fn optional_by_default(mut self) -> Self {
+        self.form.optional_by_default = true;
+        self
+    }
+
+    pub fn script(mut self, script: impl Into<String>) -> Self {
+        self.form.embedded_scripts.push(Script {
+            asset: Asset::Inline(script.into()),
+            defer: false,
+            is_async: false,
+        });
+        self
+    }
+
+    /// Adds a script loaded from `url`, like `script(src=url)`.
+    pub fn script_src(mut self, url: impl Into<String>) -> Self {
+        self.form.embedded_scripts.push(Script {
+            asset: Asset::External(ExternalAsset { url: url.into() }),
+            defer: false,
+            is_async: false,
+        });
+        self
+    }
+
+    pub fn stylesheet(mut self, stylesheet: impl Into<String>) -> Self {
+        self.form.stylesheets.push(Asset::Inline(stylesheet.into()));
+        self
+    }
+
+    /// Adds a stylesheet loaded from `url`, like `style(href=url)`.
+    pub fn stylesheet_href(mut self, url: impl Into<String>) -> Self {
+        self.form
+            .stylesheets
+            .push(Asset::External(ExternalAsset { url: url.into() }));
+        self
+    }
+
+    pub fn section(mut self, section: SectionBuilder) -> Self {
+        self.sections.push(section);
+        self
+    }
+
+    /// Builds the form, reporting every invalid attribute and unresolved
+    /// reference at once.
+    pub fn build(self) -> Result<Form, SyntacticErrors> {
+        let mut form = self.form;
+        form.backfill_descriptions();
+        let mut errors = Vec::new();
+        for section in self.sections {
+            if let Some(section) = section.build_into(&mut errors) {
+                form.sections.push(section);
+            }
+        }
+        form.apply_optional_by_default();
+        form.check_updated(&mut errors);
+        form.check_references(&mut errors);
+        form.check_duplicate_names(&mut errors);
+        finish(Some(form), errors)
+    }
+}
+
+impl Default for FormBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+enum ElementBuilder {
+    Field(FieldBuilder),
+    Group(GroupBuilder),
+}
+
+pub struct SectionBuilder {
+    attributes: Vec<OwnedAttribute>,
+    title: Option<String>,
+    instructions: Option<String>,
+    elements: Vec<ElementBuilder>,
+}
+
+impl SectionBuilder {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            attributes: vec![attribute("name", name)],
+            title: None,
+            instructions: None,
+            elements: Vec::new(),
+        }
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn instructions(mut self, instructions: impl Into<String>) -> Self {
+        self.instructions = Some(instructions.into());
+        self
+    }
+
+    /// Sets any attribute a `section` element accepts in a template.
+    pub fn attribute(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.attributes.push(attribute(name, value));
+        self
+    }
+
+    /// Sets a `data-*` attribute; `name` is given without the prefix.
+    pub fn data(self, name: &str, value: impl Into<String>) -> Self {
+        self.attribute(&format!("data-{}", name), value)
+    }
+
+    pub fn requires(self, requires: impl Into<String>) -> Self {
+        self.attribute("requires", requires)
+    }
+
+    pub fn optional(self) -> Self {
+        self.attribute("optional", "optional")
+    }
+
+    pub fn optional_if(self, optional_if: impl Into<String>) -> Self {
+        self.attribute("optional-if", optional_if)
+    }
+
+    pub fn show_if(self, show_if: impl Into<String>) -> Self {
+        self.attribute("show-if", show_if)
+    }
+
+    pub fn class(self, class: impl Into<String>) -> Self {
+        self.attribute("class", class)
+    }
+
+    pub fn audience(self, audience: impl Into<String>) -> Self {
+        self.attribute("audience", audience)
+    }
+
+    pub fn display_order(self, display_order: u16) -> Self {
+        self.attribute("display-order", display_order.to_string())
+    }
+
+    pub fn field(mut self, field: FieldBuilder) -> Self {
+        self.elements.push(ElementBuilder::Field(field));
+        self
+    }
+
+    pub fn group(mut self, group: GroupBuilder) -> Self {
+        self.elements.push(ElementBuilder::Group(group));
+        self
+    }
+
+    fn build_into(self, errors: &mut Vec<SyntacticError>) -> Option<FormSection> {
+        let mut section: FormSection = FormParser::build(self.attributes, errors)?;
+        section.title = self.title;
+        section.instructions = self.instructions;
+        for element in self.elements {
+            let element = match element {
+                ElementBuilder::Field(field) => field.build_into(errors).map(FormElement::Field),
+                ElementBuilder::Group(group) => group.build_into(errors).map(FormElement::Group),
+            };
+            section.elements.extend(element);
+        }
+        Some(section)
+    }
+
+    pub fn build(self) -> Result<FormSection, SyntacticErrors> {
+        let mut errors = Vec::new();
+        let section = self.build_into(&mut errors);
+        finish(section, errors)
+    }
+}
+
+pub struct GroupBuilder {
+    attributes: Vec<OwnedAttribute>,
+    title: Option<String>,
+    label: Option<String>,
+    instructions: Option<String>,
+    fields: Vec<FieldBuilder>,
+}
+
+impl GroupBuilder {
+    pub fn new(name: impl Into<String>, group_type: GroupType) -> Self {
+        Self {
+            attributes: vec![
+                attribute("name", name),
+                attribute("type", group_type.as_str()),
+            ],
+            title: None,
+            label: None,
+            instructions: None,
+            fields: Vec::new(),
+        }
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Labels the group for assistive technology, apart from its visible
+    /// title.
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn instructions(mut self, instructions: impl Into<String>) -> Self {
+        self.instructions = Some(instructions.into());
+        self
+    }
+
+    /// Sets any attribute a `group` element accepts in a template.
+    pub fn attribute(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.attributes.push(attribute(name, value));
+        self
+    }
+
+    /// Sets a `data-*` attribute; `name` is given without the prefix.
+    pub fn data(self, name: &str, value: impl Into<String>) -> Self {
+        self.attribute(&format!("data-{}", name), value)
+    }
+
+    pub fn repeat(self) -> Self {
+        self.attribute("repeat", "repeat")
+    }
+
+    pub fn min_repeat(self, min_repeat: u16) -> Self {
+        self.attribute("min-repeat", min_repeat.to_string())
+    }
+
+    pub fn max_repeat(self, max_repeat: u16) -> Self {
+        self.attribute("max-repeat", max_repeat.to_string())
+    }
+
+    pub fn disabled(self) -> Self {
+        self.attribute("disabled", "disabled")
+    }
+
+    pub fn requires(self, requires: impl Into<String>) -> Self {
+        self.attribute("requires", requires)
+    }
+
+    pub fn optional(self) -> Self {
+        self.attribute("optional", "optional")
+    }
+
+    pub fn optional_if(self, optional_if: impl Into<String>) -> Self {
+        self.attribute("optional-if", optional_if)
+    }
+
+    pub fn show_if(self, show_if: impl Into<String>) -> Self {
+        self.attribute("show-if", show_if)
+    }
+
+    pub fn class(self, class: impl Into<String>) -> Self {
+        self.attribute("class", class)
+    }
+
+    pub fn audience(self, audience: impl Into<String>) -> Self {
+        self.attribute("audience", audience)
+    }
+
+    pub fn field(mut self, field: FieldBuilder) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    fn build_into(self, errors: &mut Vec<SyntacticError>) -> Option<FormGroup> {
+        let mut group: FormGroup = FormParser::build(self.attributes, errors)?;
+        group.title = self.title;
+        group.label = self.label;
+        group.instructions = self.instructions;
+        for field in self.fields {
+            group.members.extend(field.build_into(errors));
+        }
+        Some(group)
+    }
+
+    pub fn build(self) -> Result<FormGroup, SyntacticErrors> {
+        let mut errors = Vec::new();
+        let group = self.build_into(&mut errors);
+        finish(group, errors)
+    }
+}
+
+pub struct FieldBuilder {
+    attributes: Vec<OwnedAttribute>,
+    label: Option<String>,
+    instructions: Option<String>,
+    options: Vec<OptionBuilder>,
+}
+
+impl FieldBuilder {
+    pub fn new(name: impl Into<String>, field_type: FieldType) -> Self {
+        Self {
+            attributes: vec![
+                attribute("name", name),
+                attribute("type", field_type.as_str()),
+            ],
+            label: None,
+            instructions: None,
+            options: Vec::new(),
+        }
+    }
+
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn instructions(mut self, instructions: impl Into<String>) -> Self {
+        self.instructions = Some(instructions.into());
+        self
+    }
+
+    /// Sets any attribute a `field` element accepts in a template.
+    pub fn attribute(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.attributes.push(attribute(name, value));
+        self
+    }
+
+    /// Sets a `data-*` attribute; `name` is given without the prefix.
+    pub fn data(self, name: &str, value: impl Into<String>) -> Self {
+        self.attribute(&format!("data-{}", name), value)
+    }
+
+    pub fn placeholder(self, placeholder: impl Into<String>) -> Self {
+        self.attribute("placeholder", placeholder)
+    }
+
+    pub fn pattern(self, pattern: impl Into<String>) -> Self {
+        self.attribute("pattern", pattern)
+    }
+
+    pub fn autocomplete(self, autocomplete: impl Into<String>) -> Self {
+        self.attribute("autocomplete", autocomplete)
+    }
+
+    pub fn length(self, length: u16) -> Self {
+        self.attribute("length", length.to_string())
+    }
+
+    pub fn rows(self, rows: &[u16]) -> Self {
+        let rows: Vec<String> = rows.iter().map(u16::to_string).collect();
+        self.attribute("rows", rows.join(" "))
+    }
+
+    pub fn columns(self, columns: &[u16]) -> Self {
+        let columns: Vec<String> = columns.iter().map(u16::to_string).collect();
+        self.attribute("columns", columns.join(" "))
+    }
+
+    pub fn accept(self, accept: impl Into<String>) -> Self {
+        self.attribute("accept", accept)
+    }
+
+    pub fn multiple(self) -> Self {
+        self.attribute("multiple", "multiple")
+    }
+
+    pub fn readonly(self) -> Self {
+        self.attribute("readonly", "readonly")
+    }
+
+    pub fn disabled(self) -> Self {
+        self.attribute("disabled", "disabled")
+    }
+
+    pub fn min_selections(self, min_selections: u16) -> Self {
+        self.attribute("min-selections", min_selections.to_string())
+    }
+
+    pub fn max_selections(self, max_selections: u16) -> Self {
+        self.attribute("max-selections", max_selections.to_string())
+    }
+
+    pub fn minlength(self, minlength: u16) -> Self {
+        self.attribute("minlength", minlength.to_string())
+    }
+
+    pub fn maxlength(self, maxlength: u16) -> Self {
+        self.attribute("maxlength", maxlength.to_string())
+    }
+
+    pub fn min(self, min: f64) -> Self {
+        self.attribute("min", min.to_string())
+    }
+
+    pub fn max(self, max: f64) -> Self {
+        self.attribute("max", max.to_string())
+    }
+
+    pub fn step(self, step: f64) -> Self {
+        self.attribute("step", step.to_string())
+    }
+
+    /// The value a color field starts out with, e.g. `#ff0000`.
+    pub fn default_value(self, default: impl Into<String>) -> Self {
+        self.attribute("default", default)
+    }
+
+    pub fn min_words(self, min_words: u16) -> Self {
+        self.attribute("min-words", min_words.to_string())
+    }
+
+    pub fn max_words(self, max_words: u16) -> Self {
+        self.attribute("max-words", max_words.to_string())
+    }
+
+    /// Loads options from a YAML or JSON file, relative to the current
+    /// directory, ahead of those added with `option`.
+    pub fn options_from(self, path: impl Into<String>) -> Self {
+        self.attribute("options-from", path)
+    }
+
+    pub fn requires(self, requires: impl Into<String>) -> Self {
+        self.attribute("requires", requires)
+    }
+
+    pub fn optional(self) -> Self {
+        self.attribute("optional", "optional")
+    }
+
+    /// Marks the field as required in a form that is optional by default.
+    pub fn required(self) -> Self {
+        self.attribute("required", "required")
+    }
+
+    pub fn optional_if(self, optional_if: impl Into<String>) -> Self {
+        self.attribute("optional-if", optional_if)
+    }
+
+    pub fn show_if(self, show_if: impl Into<String>) -> Self {
+        self.attribute("show-if", show_if)
+    }
+
+    pub fn class(self, class: impl Into<String>) -> Self {
+        self.attribute("class", class)
+    }
+
+    pub fn audience(self, audience: impl Into<String>) -> Self {
+        self.attribute("audience", audience)
+    }
+
+    pub fn option(self, name: impl Into<String>, label: impl Into<String>) -> Self {
+        self.options(Some(OptionBuilder::new(name).label(label)))
+    }
+
+    /// Adds options with attributes of their own.
+    pub fn options(mut self, options: impl IntoIterator<Item = OptionBuilder>) -> Self {
+        self.options.extend(options);
+        self
+    }
+
+    fn build_into(self, errors: &mut Vec<SyntacticError>) -> Option<FormField> {
+        let mut field: FormField = FormParser::build(self.attributes, errors)?;
+        field.label = self.label;
+        field.instructions = self.instructions;
+        if !self.options.is_empty() {
+            if let Err(e) = field.check_accepts_options("options") {
+                errors.push(e);
+            }
+        }
+        for option in self.options {
+            field.options.extend(option.build_into(errors));
+        }
+        if let Err(e) = field.load_options(Path::new("")) {
+            errors.push(e);
+        }
+        if let Err(e) = field.check_selections() {
+            errors.push(e);
+        }
+        Some(field)
+    }
+
+    pub fn build(self) -> Result<FormField, SyntacticErrors> {
+        let mut errors = Vec::new();
+        let field = self.build_into(&mut errors);
+        finish(field, errors)
+    }
+}
+
+pub struct OptionBuilder {
+    attributes: Vec<OwnedAttribute>,
+    label: Option<String>,
+    optgroup: Option<String>,
+}
+
+impl OptionBuilder {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            attributes: vec![attribute("name", name)],
+            label: None,
+            optgroup: None,
+        }
+    }
+
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Lists the option under an `optgroup` with this label.
+    pub fn optgroup(mut self, label: impl Into<String>) -> Self {
+        self.optgroup = Some(label.into());
+        self
+    }
+
+    /// Sets any attribute an `option` element accepts in a template.
+    pub fn attribute(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.attributes.push(attribute(name, value));
+        self
+    }
+
+    /// Sets a `data-*` attribute; `name` is given without the prefix.
+    pub fn data(self, name: &str, value: impl Into<String>) -> Self {
+        self.attribute(&format!("data-{}", name), value)
+    }
+
+    pub fn show_if(self, show_if: impl Into<String>) -> Self {
+        self.attribute("show-if", show_if)
+    }
+
+    pub fn class(self, class: impl Into<String>) -> Self {
+        self.attribute("class", class)
+    }
+
+    pub fn audience(self, audience: impl Into<String>) -> Self {
+        self.attribute("audience", audience)
+    }
+
+    fn build_into(self, errors: &mut Vec<SyntacticError>) -> Option<FieldOption> {
+        let mut option: FieldOption = FormParser::build(self.attributes, errors)?;
+        option.label = self.label;
+        option.optgroup = self.optgroup;
+        Some(option)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_parsed_form() {
+        let parsed = Form::try_from(String::from(
+            r#"<form>
+                <title>Residence</title>
+                <section name="applicant">
+                    <field name="surname" type="text"><label>Surname</label></field>
+                    <field name="status" type="select">
+                        <option name="single">Single</option>
+                        <option name="married">Married</option>
+                    </field>
+                    <field name="spouse" type="text" requires="status.married" optional="optional"/>
+                </section>
+            </form>"#,
+        ))
+        .unwrap();
+
+        let built = FormBuilder::new()
+            .title("Residence")
+            .section(
+                SectionBuilder::new("applicant")
+                    .field(FieldBuilder::new("surname", FieldType::Text).label("Surname"))
+                    .field(
+                        FieldBuilder::new("status", FieldType::Select)
+                            .option("single", "Single")
+                            .option("married", "Married"),
+                    )
+                    .field(
+                        FieldBuilder::new("spouse", FieldType::Text)
+                            .requires("status.married")
+                            .optional(),
+                    ),
+            )
+            .build()
+            .unwrap();
+
+        assert!(built.structurally_eq(&parsed));
+    }
+
+    #[test]
+    fn serializes_like_fixture() {
+        let compiled = compile("resources/accessors.pug").unwrap();
+        let built = FormBuilder::new()
+            .title("Vehicle Registration")
+            .language("en")
+            .section(
+                SectionBuilder::new("vehicle")
+                    .class("wide")
+                    .title("Vehicle")
+                    .field(
+                        FieldBuilder::new("kind", FieldType::Select)
+                            .label("Kind of vehicle")
+                            .options(vec![
+                                OptionBuilder::new("car").label("Car"),
+                                OptionBuilder::new("truck").label("Truck"),
+                            ]),
+                    )
+                    .group(
+                        GroupBuilder::new("plate", GroupType::Row)
+                            .field(
+                                FieldBuilder::new("plate-number", FieldType::Text)
+                                    .length(10)
+                                    .placeholder("AB 1234")
+                                    .label("Plate number"),
+                            )
+                            .field(
+                                FieldBuilder::new("plate-region", FieldType::Text)
+                                    .optional()
+                                    .requires("plate-number")
+                                    .label("Region"),
+                            ),
+                    ),
+            )
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            serde_json::to_string(&built).unwrap(),
+            serde_json::to_string(&compiled).unwrap()
+        );
+    }
+
+    #[test]
+    fn options_only_on_selects() {
+        let errors = FieldBuilder::new("surname", FieldType::Text)
+            .option("smith", "Smith")
+            .build()
+            .unwrap_err();
+        assert_eq!(errors.0.len(), 1);
+        assert!(matches!(
+            errors.0[0].error,
+            SyntacticError::ImproperNesting { .. }
+        ));
+    }
+
+    #[test]
+    fn invalid_fields() {
+        let errors = FormBuilder::new()
+            .section(
+                SectionBuilder::new("applicant")
+                    .field(FieldBuilder::new("surname", FieldType::Text).accept("image/png"))
+                    .field(FieldBuilder::new("spouse", FieldType::Text).requires("status")),
+            )
+            .build()
+            .unwrap_err();
+        assert_eq!(errors.0.len(), 2);
+        assert!(matches!(
+            errors.0[0].error,
+            SyntacticError::InvalidAttribute { .. }
+        ));
+        assert!(matches!(
+            errors.0[1].error,
+            SyntacticError::UnresolvedReference { .. }
+        ));
+    }
+}
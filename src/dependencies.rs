@@ -0,0 +1,162 @@
+//! Finding the files a form is compiled from.
+//!
+//! A form is only up to date if none of the files it `include`s or `extends`
+//! has changed either. These are found by scanning the pug source for those
+//! statements directly, which is much cheaper than running pug.
+//!
+//! Relative paths are resolved against the directory of the file containing
+//! the statement and absolute paths are taken as is. Paths without an
+//! extension get pug's implicit `.pug`. Only pug files are scanned in turn;
+//! anything else is included as raw text.
+
+use super::*;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum DependencyError {
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    MissingInclude {
+        including: PathBuf,
+        include: PathBuf,
+    },
+    Cycle {
+        files: Vec<PathBuf>,
+    },
+}
+
+impl fmt::Display for DependencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self {
+            DependencyError::Io { path, error } => {
+                write!(f, "could not read {}: {}", path.display(), error)
+            }
+            DependencyError::MissingInclude { including, include } => write!(
+                f,
+                "{} includes {}, which does not exist",
+                including.display(),
+                include.display()
+            ),
+            DependencyError::Cycle { files } => {
+                let files: Vec<_> = files
+                    .iter()
+                    .map(|file| file.display().to_string())
+                    .collect();
+                write!(f, "include cycle: {}", files.join(" -> "))
+            }
+        }
+    }
+}
+
+impl error::Error for DependencyError {}
+
+/// The path named by an `include` or `extends` statement on `line`, if any.
+fn referenced_path(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    let rest = if let Some(rest) = line.strip_prefix("include") {
+        // filtered includes look like `include:markdown-it(option) file.md`
+        match rest.strip_prefix(':') {
+            Some(filtered) => &filtered[filtered.find(char::is_whitespace)?..],
+            None => rest,
+        }
+    } else {
+        line.strip_prefix("extends")?
+    };
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let path = rest.trim();
+    if path.is_empty() {
+        None
+    } else {
+        Some(path)
+    }
+}
+
+fn resolve(including: &Path, include: &str) -> PathBuf {
+    let mut path = including
+        .parent()
+        .map(|dir| dir.join(include))
+        .unwrap_or_else(|| PathBuf::from(include));
+    if path.extension().is_none() {
+        path.set_extension("pug");
+    }
+    path
+}
+
+fn read(path: &Path) -> Result<String, DependencyError> {
+    fs::read_to_string(path).map_err(|error| DependencyError::Io {
+        path: path.to_path_buf(),
+        error,
+    })
+}
+
+fn canonicalize(path: &Path) -> Result<PathBuf, DependencyError> {
+    fs::canonicalize(path).map_err(|error| DependencyError::Io {
+        path: path.to_path_buf(),
+        error,
+    })
+}
+
+/// Depth-first walk over the includes of `file`, with `stack` holding the
+/// files currently being scanned.
+fn visit(
+    file: &Path,
+    stack: &mut Vec<PathBuf>,
+    dependencies: &mut Vec<PathBuf>,
+) -> Result<(), DependencyError> {
+    let source = read(file)?;
+    for include in source.lines().filter_map(referenced_path) {
+        let path = resolve(file, include);
+        if !path.exists() {
+            return Err(DependencyError::MissingInclude {
+                including: file.to_path_buf(),
+                include: path,
+            });
+        }
+        let path = canonicalize(&path)?;
+        if let Some(start) = stack.iter().position(|ancestor| *ancestor == path) {
+            let mut files = stack[start..].to_vec();
+            files.push(path);
+            return Err(DependencyError::Cycle { files });
+        }
+        if dependencies.contains(&path) {
+            continue;
+        }
+        dependencies.push(path.clone());
+        if path.extension().and_then(|extension| extension.to_str()) == Some("pug") {
+            stack.push(path.clone());
+            visit(&path, stack, dependencies)?;
+            stack.pop();
+        }
+    }
+    Ok(())
+}
+
+/// Every file `source` includes or extends, directly or indirectly, in the
+/// order they are first referenced.
+pub fn discover_dependencies(source: impl AsRef<Path>) -> Result<Vec<PathBuf>, DependencyError> {
+    let source = canonicalize(source.as_ref())?;
+    let mut dependencies = Vec::new();
+    visit(&source, &mut vec![source.clone()], &mut dependencies)?;
+    Ok(dependencies)
+}
+
+/// A hex-encoded SHA-256 hash of the contents of `source` and all of its
+/// dependencies. It changes whenever any of them does, so it can be used to
+/// decide whether a compiled form is stale.
+pub fn source_fingerprint(source: impl AsRef<Path>) -> Result<String, DependencyError> {
+    let source = source.as_ref();
+    let mut hasher = Sha256::new();
+    for file in std::iter::once(source.to_path_buf()).chain(discover_dependencies(source)?) {
+        let contents =
+            fs::read(&file).map_err(|error| DependencyError::Io { path: file, error })?;
+        hasher.update((contents.len() as u64).to_le_bytes());
+        hasher.update(&contents);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
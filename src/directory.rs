@@ -0,0 +1,310 @@
+//! Compiling every form in a directory.
+//!
+//! Only files named `*.mf.pug` are compiled, so that the partials and layouts
+//! they include can sit next to them. Files are compiled in order of their
+//! path, and every error is wrapped in `CompileError::File` to say which file
+//! it came from.
+//!
+//! [`build_catalog`] goes a step further and orders the forms the way a
+//! directory page lists them, by `index` and then `title`.
+
+use super::*;
+use std::fs;
+
+const FORM_SUFFIX: &str = ".mf.pug";
+
+/// How `compile_dir_with_options` walks a directory.
+#[derive(Debug, Clone, Default)]
+pub struct DirOptions {
+    recursive: bool,
+    continue_on_error: bool,
+    compile_options: CompileOptions,
+}
+
+impl DirOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also compile forms in subdirectories.
+    pub fn recursive(mut self) -> Self {
+        self.recursive = true;
+        self
+    }
+
+    /// Keep going after a form fails to compile and collect its error in
+    /// `CompiledDir::errors`, or `Catalog::errors`, rather than returning it.
+    pub fn continue_on_error(mut self) -> Self {
+        self.continue_on_error = true;
+        self
+    }
+
+    /// The options each form is compiled with.
+    pub fn compile_options(mut self, compile_options: CompileOptions) -> Self {
+        self.compile_options = compile_options;
+        self
+    }
+}
+
+/// The forms compiled from a directory, each with the file it came from.
+#[derive(Debug, Default)]
+pub struct CompiledDir {
+    pub forms: Vec<(PathBuf, Form)>,
+    /// Only ever non-empty with `DirOptions::continue_on_error`.
+    pub errors: Vec<CompileError>,
+}
+
+fn in_file(path: &Path, error: impl Into<CompileError>) -> CompileError {
+    CompileError::File {
+        path: path.to_path_buf(),
+        error: Box::new(error.into()),
+    }
+}
+
+/// The forms in `dir`, and with `recursive` in its subdirectories, sorted by
+/// path.
+fn find_forms(dir: &Path, recursive: bool, forms: &mut Vec<PathBuf>) -> Result<(), CompileError> {
+    for entry in fs::read_dir(dir).map_err(|e| in_file(dir, e))? {
+        let path = entry.map_err(|e| in_file(dir, e))?.path();
+        if path.is_dir() {
+            if recursive {
+                find_forms(&path, recursive, forms)?;
+            }
+        } else if path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| name.ends_with(FORM_SUFFIX))
+            == Some(true)
+        {
+            forms.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Compiles every `*.mf.pug` file in `dir`, not including subdirectories,
+/// and stops at the first that fails.
+pub fn compile_dir(dir: impl Into<PathBuf>) -> Result<Vec<(PathBuf, Form)>, CompileError> {
+    let compiled = compile_dir_with_options(dir, &DirOptions::new())?;
+    Ok(compiled.forms)
+}
+
+pub fn compile_dir_with_options(
+    dir: impl Into<PathBuf>,
+    options: &DirOptions,
+) -> Result<CompiledDir, CompileError> {
+    let mut paths = Vec::new();
+    find_forms(&dir.into(), options.recursive, &mut paths)?;
+    paths.sort();
+
+    let mut compiled = CompiledDir::default();
+    for path in paths {
+        match compile_with_options(&path, &options.compile_options) {
+            Ok(form) => compiled.forms.push((path, form)),
+            Err(e) if options.continue_on_error => compiled.errors.push(in_file(&path, e)),
+            Err(e) => return Err(in_file(&path, e)),
+        }
+    }
+    Ok(compiled)
+}
+
+/// The forms of a directory in the order they are listed, see
+/// [`build_catalog`].
+#[derive(Debug, Default)]
+pub struct Catalog {
+    forms: Vec<(PathBuf, Form)>,
+    warnings: Vec<CompileWarning>,
+    errors: Vec<CompileError>,
+}
+
+impl Catalog {
+    /// Sorts `forms` by `index`, with forms without one last, then by title.
+    /// Forms with the same index are warned about, or with `strict` make it
+    /// an error.
+    fn new(mut forms: Vec<(PathBuf, Form)>, strict: bool) -> Result<Self, CompileError> {
+        forms.sort_by(|(_, a), (_, b)| {
+            (a.index.is_none(), a.index, &a.title).cmp(&(b.index.is_none(), b.index, &b.title))
+        });
+
+        let mut warnings = Vec::new();
+        for pair in forms.windows(2) {
+            let ((first, a), (second, b)) = (&pair[0], &pair[1]);
+            if let Some(index) = a.index.filter(|&index| b.index == Some(index)) {
+                let (first, second) = (first.clone(), second.clone());
+                if strict {
+                    return Err(CompileError::DuplicateIndex {
+                        index,
+                        first,
+                        second,
+                    });
+                }
+                warnings.push(CompileWarning::DuplicateIndex {
+                    index,
+                    first,
+                    second,
+                });
+            }
+        }
+        Ok(Catalog {
+            forms,
+            warnings,
+            errors: Vec::new(),
+        })
+    }
+
+    /// Every form, unlisted ones included, in order.
+    pub fn forms(&self) -> &[(PathBuf, Form)] {
+        &self.forms
+    }
+
+    /// Forms sharing an `index`.
+    pub fn warnings(&self) -> &[CompileWarning] {
+        &self.warnings
+    }
+
+    /// The forms that failed to compile, only ever non-empty with
+    /// `DirOptions::continue_on_error`. They are left out of the catalog.
+    pub fn errors(&self) -> &[CompileError] {
+        &self.errors
+    }
+
+    /// The forms that aren't `unlisted`, in order.
+    pub fn listed(&self) -> impl Iterator<Item = (&Path, &Form)> {
+        self.forms
+            .iter()
+            .filter(|(_, form)| !form.unlisted)
+            .map(|(path, form)| (path.as_path(), form))
+    }
+
+    /// The listed forms grouped by `category`, with the categories in
+    /// alphabetical order after the forms that have none. Each group is in
+    /// order.
+    pub fn by_category(&self) -> impl Iterator<Item = (Option<&str>, Vec<(&Path, &Form)>)> {
+        let mut categories: BTreeMap<Option<&str>, Vec<(&Path, &Form)>> = BTreeMap::new();
+        for (path, form) in self.listed() {
+            categories
+                .entry(form.category())
+                .or_default()
+                .push((path, form));
+        }
+        categories.into_iter()
+    }
+}
+
+/// Compiles every `*.mf.pug` file in `dir` like [`compile_dir`] and orders
+/// them into a [`Catalog`].
+pub fn build_catalog(dir: impl Into<PathBuf>) -> Result<Catalog, CompileError> {
+    build_catalog_with_options(dir, &DirOptions::new())
+}
+
+/// Like [`build_catalog`]. With [`CompileOptions::strict`], two forms with
+/// the same `index` are an error rather than a warning.
+pub fn build_catalog_with_options(
+    dir: impl Into<PathBuf>,
+    options: &DirOptions,
+) -> Result<Catalog, CompileError> {
+    let compiled = compile_dir_with_options(dir, options)?;
+    let mut catalog = Catalog::new(compiled.forms, options.compile_options.strict)?;
+    catalog.errors = compiled.errors;
+    Ok(catalog)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn form(path: &str, xml: &str) -> (PathBuf, Form) {
+        (PathBuf::from(path), compile_xml_str(xml).unwrap())
+    }
+
+    fn forms() -> Vec<(PathBuf, Form)> {
+        vec![
+            form("a.mf.pug", "<form><title>Zoning</title></form>"),
+            form(
+                "b.mf.pug",
+                "<form><title>Parking</title><index>2</index><category>Roads</category></form>",
+            ),
+            form(
+                "c.mf.pug",
+                "<form><title>Drafts</title><index>1</index><unlisted/></form>",
+            ),
+            form(
+                "d.mf.pug",
+                "<form><title>Bins</title><index>2</index></form>",
+            ),
+        ]
+    }
+
+    #[test]
+    fn catalog() {
+        let catalog = Catalog::new(forms(), false).unwrap();
+        let titles: Vec<_> = catalog
+            .forms()
+            .iter()
+            .map(|(_, form)| form.title().unwrap())
+            .collect();
+        assert_eq!(titles, ["Drafts", "Bins", "Parking", "Zoning"]);
+
+        let listed: Vec<_> = catalog.listed().map(|(path, _)| path).collect();
+        assert_eq!(
+            listed,
+            [
+                Path::new("d.mf.pug"),
+                Path::new("b.mf.pug"),
+                Path::new("a.mf.pug")
+            ]
+        );
+
+        let categories: Vec<_> = catalog
+            .by_category()
+            .map(|(category, forms)| (category, forms.len()))
+            .collect();
+        assert_eq!(categories, [(None, 2), (Some("Roads"), 1)]);
+
+        assert!(matches!(
+            catalog.warnings(),
+            [CompileWarning::DuplicateIndex { index: 2, first, second }]
+                if first == Path::new("d.mf.pug") && second == Path::new("b.mf.pug")
+        ));
+    }
+
+    #[test]
+    fn strict_catalog() {
+        assert!(matches!(
+            Catalog::new(forms(), true),
+            Err(CompileError::DuplicateIndex { index: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn catalog_from_directory() {
+        // options.mf.pug and tax-patent.mf.pug are fixtures for errors;
+        // select-group.mf.pug has no title, so it comes first
+        let error = build_catalog("resources").unwrap_err();
+        assert!(matches!(error, CompileError::File { ref path, .. }
+            if path.ends_with("options.mf.pug")));
+
+        let catalog =
+            build_catalog_with_options("resources", &DirOptions::new().continue_on_error())
+                .unwrap();
+        assert_eq!(catalog.forms().len(), 4);
+        let listed: Vec<_> = catalog
+            .listed()
+            .map(|(path, _)| path.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(
+            listed,
+            [
+                "select-group.mf.pug",
+                "multiple-scripts.mf.pug",
+                "trademark-application.mf.pug",
+            ]
+        );
+        assert!(catalog.warnings().is_empty());
+        assert_eq!(catalog.errors().len(), 2);
+        assert!(
+            matches!(catalog.errors()[0], CompileError::File { ref path, .. }
+            if path.ends_with("options.mf.pug"))
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use super::*;
+use std::fmt::Write;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Warning,
+}
+
+/// A likely mistake in a form that is nonetheless valid.
+#[derive(Serialize, Debug)]
+pub struct Lint {
+    pub severity: Severity,
+    pub message: String,
+    pub paths: Vec<String>,
+}
+
+impl FormField {
+    // everything that makes up a field except its name and presentation;
+    // attributes added to fields belong here unless they are presentation
+    fn shape(&self) -> String {
+        let mut shape = format!(
+            "{:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {} {:?} {:?} {:?} {:?} {} {} {:?} {} {:?} {:?} {:?}",
+            self.field_type,
+            self.length,
+            self.minlength,
+            self.maxlength,
+            self.pattern,
+            self.autocomplete,
+            self.min,
+            self.max,
+            self.step,
+            self.rows,
+            self.columns,
+            self.accept,
+            self.multiple,
+            self.min_selections,
+            self.max_selections,
+            self.min_words,
+            self.max_words,
+            self.readonly,
+            self.disabled,
+            self.attributes.requires,
+            self.attributes.required,
+            self.attributes.optional,
+            self.attributes.optional_if,
+            self.attributes.show_if,
+        );
+        for option in &self.options {
+            write!(shape, " {:?}", option.name).unwrap();
+        }
+        shape
+    }
+}
+
+fn numeric_stem(name: &str) -> Option<&str> {
+    let stem = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    if stem.len() < name.len() {
+        Some(stem.trim_end_matches(&['-', '_'][..]))
+    } else {
+        None
+    }
+}
+
+impl Form {
+    fn fields_with_paths(&self) -> Vec<(String, &FormField)> {
+        self.fields_with_path()
+            .map(|(section, group, field)| {
+                let path = match group {
+                    Some(group) => format!(
+                        "section '{}' > group '{}' > field '{}'",
+                        section, group, field.name
+                    ),
+                    None => format!("section '{}' > field '{}'", section, field.name),
+                };
+                (path, field)
+            })
+            .collect()
+    }
+
+    /// Looks for select and multi-select fields without options, which
+    /// [`Form::validate`] rejects but compiling does not, and for fields that
+    /// were probably copied and not fully edited: fields with the same label
+    /// but different names, and fields whose names differ only by a numeric
+    /// suffix while being otherwise identical.
+    ///
+    /// Either field of a pair can opt out with a `data-allow-duplicate`
+    /// attribute. Labels of hidden fields are never shown, so they are not
+    /// compared.
+    pub fn lint(&self) -> Vec<Lint> {
+        let fields = self.fields_with_paths();
+        let mut lints = Vec::new();
+
+        for (path, field) in &fields {
+            if matches!(field.field_type, FieldType::Select | FieldType::MultiSelect)
+                && field.options.is_empty()
+            {
+                lints.push(Lint {
+                    severity: Severity::Warning,
+                    message: format!(
+                        "{} field {} has no options",
+                        field.field_type.as_str(),
+                        field.name
+                    ),
+                    paths: vec![path.clone()],
+                });
+            }
+        }
+
+        for (i, (path, field)) in fields.iter().enumerate() {
+            for (other_path, other) in &fields[i + 1..] {
+                if field.attributes.data.contains_key("allow-duplicate")
+                    || other.attributes.data.contains_key("allow-duplicate")
+                {
+                    continue;
+                }
+                if field.name == other.name {
+                    continue;
+                }
+
+                let hidden =
+                    field.field_type == FieldType::Hidden || other.field_type == FieldType::Hidden;
+                let same_label = match (&field.label, &other.label) {
+                    _ if hidden => false,
+                    (Some(label), Some(other_label)) => {
+                        !label.trim().is_empty() && label.trim() == other_label.trim()
+                    }
+                    _ => false,
+                };
+                let same_stem = match (numeric_stem(&field.name), numeric_stem(&other.name)) {
+                    (Some(stem), Some(other_stem)) => stem == other_stem,
+                    (Some(stem), None) => stem == other.name,
+                    (None, Some(other_stem)) => field.name == other_stem,
+                    (None, None) => false,
+                } && field.shape() == other.shape();
+
+                if same_label || same_stem {
+                    lints.push(Lint {
+                        severity: Severity::Info,
+                        message: format!(
+                            "possible copy-paste duplicate: {} and {}",
+                            field.name, other.name
+                        ),
+                        paths: vec![path.clone(), other_path.clone()],
+                    });
+                }
+            }
+        }
+        lints
+    }
+}
@@ -0,0 +1,183 @@
+//! Pseudo-localization for testing how forms hold up in translation.
+//!
+//! Every translatable string is rewritten with accented look-alikes of its
+//! letters and lengthened by about 30%, which makes hardcoded strings stand
+//! out and shows where layouts truncate longer translations. The result is
+//! still readable, and the same input always gives the same output.
+
+use super::*;
+
+/// The pseudo-locale reserved for this purpose by Windows and ICU.
+const PSEUDO_LOCALE: &str = "qps-ploc";
+
+fn accented(c: char) -> char {
+    match c {
+        'a' => 'å',
+        'b' => 'ƀ',
+        'c' => 'ç',
+        'd' => 'ð',
+        'e' => 'é',
+        'f' => 'ƒ',
+        'g' => 'ĝ',
+        'h' => 'ĥ',
+        'i' => 'î',
+        'j' => 'ĵ',
+        'k' => 'ķ',
+        'l' => 'ļ',
+        'm' => 'ɱ',
+        'n' => 'ñ',
+        'o' => 'ö',
+        'p' => 'þ',
+        'q' => 'ǫ',
+        'r' => 'ŕ',
+        's' => 'š',
+        't' => 'ţ',
+        'u' => 'û',
+        'v' => 'ṽ',
+        'w' => 'ŵ',
+        'x' => 'ẋ',
+        'y' => 'ý',
+        'z' => 'ž',
+        'A' => 'Å',
+        'B' => 'Ɓ',
+        'C' => 'Ç',
+        'D' => 'Ð',
+        'E' => 'É',
+        'F' => 'Ƒ',
+        'G' => 'Ĝ',
+        'H' => 'Ĥ',
+        'I' => 'Î',
+        'J' => 'Ĵ',
+        'K' => 'Ķ',
+        'L' => 'Ļ',
+        'M' => 'Ṁ',
+        'N' => 'Ñ',
+        'O' => 'Ö',
+        'P' => 'Þ',
+        'Q' => 'Ǫ',
+        'R' => 'Ŕ',
+        'S' => 'Š',
+        'T' => 'Ţ',
+        'U' => 'Û',
+        'V' => 'Ṽ',
+        'W' => 'Ŵ',
+        'X' => 'Ẋ',
+        'Y' => 'Ý',
+        'Z' => 'Ž',
+        c => c,
+    }
+}
+
+/// Accents a run of text and pads it by a little over 30% of its length. The
+/// padding goes before any trailing whitespace. In markup, entities like
+/// `&amp;` are left intact.
+fn pseudolocalize_text(text: &str, markup: bool, out: &mut String) {
+    let content = text.trim_end();
+    if content.trim_start().is_empty() {
+        out.push_str(text);
+        return;
+    }
+
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        out.push(accented(c));
+        if markup && c == '&' {
+            while let Some(c) = chars.next_if(|c| *c != ';' && !c.is_whitespace()) {
+                out.push(c);
+            }
+        }
+    }
+    let length = content.trim_start().chars().count();
+    out.push_str(&"~".repeat(length * 3 / 10 + 1));
+    out.push_str(&text[content.len()..]);
+}
+
+/// Like `pseudolocalize_text`, but only for the text between tags.
+fn pseudolocalize_markup(markup: &str) -> String {
+    let mut out = String::with_capacity(markup.len() * 2);
+    let mut rest = markup;
+    while !rest.is_empty() {
+        let text_end = rest.find('<').unwrap_or(rest.len());
+        pseudolocalize_text(&rest[..text_end], true, &mut out);
+        rest = &rest[text_end..];
+
+        let tag_end = rest.find('>').map_or(rest.len(), |i| i + 1);
+        out.push_str(&rest[..tag_end]);
+        rest = &rest[tag_end..];
+    }
+    out
+}
+
+fn pseudolocalize(text: &mut Option<String>) {
+    if let Some(text) = text {
+        let mut out = String::with_capacity(text.len() * 2);
+        pseudolocalize_text(text, false, &mut out);
+        *text = out;
+    }
+}
+
+fn pseudolocalize_instructions(instructions: &mut Option<String>) {
+    if let Some(instructions) = instructions {
+        *instructions = pseudolocalize_markup(instructions);
+    }
+}
+
+impl FormField {
+    fn pseudolocalize(&mut self) {
+        pseudolocalize(&mut self.label);
+        pseudolocalize(&mut self.placeholder);
+        pseudolocalize_instructions(&mut self.instructions);
+        for option in &mut self.options {
+            pseudolocalize(&mut option.label);
+            pseudolocalize(&mut option.optgroup);
+        }
+    }
+}
+
+impl Form {
+    /// A copy of the form in the `qps-ploc` pseudo-locale.
+    ///
+    /// Titles, descriptions, labels, placeholders, option, optgroup, link and
+    /// submit button labels, contact hours and notes and the text of
+    /// instructions are pseudo-localized; markup in instructions is kept as
+    /// is. Names, conditions, classes and everything else a submission or the
+    /// frontend depends on are left untouched.
+    pub fn pseudolocalize(&self) -> Form {
+        let mut form = self.clone();
+        pseudolocalize(&mut form.title);
+        pseudolocalize(&mut form.description);
+        pseudolocalize(&mut form.meta_description);
+        pseudolocalize(&mut form.dir_description);
+        pseudolocalize_instructions(&mut form.instructions);
+        form.language = Some(String::from(PSEUDO_LOCALE));
+        for link in &mut form.links {
+            pseudolocalize(&mut link.label);
+        }
+        if let Some(ref mut submit) = form.submit {
+            pseudolocalize(&mut submit.label);
+        }
+        if let Some(ref mut contact) = form.contact {
+            pseudolocalize(&mut contact.hours);
+            pseudolocalize(&mut contact.note);
+        }
+
+        for section in &mut form.sections {
+            pseudolocalize(&mut section.title);
+            pseudolocalize_instructions(&mut section.instructions);
+            for element in &mut section.elements {
+                match element {
+                    FormElement::Field(field) => field.pseudolocalize(),
+                    FormElement::Group(group) => {
+                        pseudolocalize(&mut group.title);
+                        pseudolocalize(&mut group.label);
+                        pseudolocalize_instructions(&mut group.instructions);
+                        for field in &mut group.members {
+                            field.pseudolocalize();
+                        }
+                    }
+                }
+            }
+        }
+        form
+    }
+}
@@ -0,0 +1,589 @@
+//! Rendering a form as HTML.
+//!
+//! Sections become `section` elements and groups `fieldset`s, each field gets
+//! an input matching its type, wired to its label with `for`, and `class` and
+//! `data-*` attributes are passed through. Fields are `required` unless they,
+//! their group or their section are optional or only asked for under some
+//! condition, see [`Form::requiredness`]; conditions are left to the page's
+//! scripts. A required checkbox has to be ticked, as
+//! [`Form::validate_submission`] expects.
+//!
+//! Ids are the field name prefixed with the section name, e.g.
+//! `applicant-surname`. Field names are unique within a form, except that
+//! members of different repeat groups may share one.
+
+use super::*;
+
+/// How [`Form::to_html`] renders a form's stylesheet and scripts.
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    asset_url: Option<String>,
+    omit_assets: bool,
+}
+
+impl RenderOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Where extracted assets are served from. They are linked as this URL
+    /// followed by their file name, rather than by the path they were
+    /// extracted to. Inline assets are always embedded.
+    pub fn asset_url(mut self, asset_url: impl Into<String>) -> Self {
+        self.asset_url = Some(asset_url.into());
+        self
+    }
+
+    /// Leave out the stylesheet and scripts, e.g. when the page includes them
+    /// itself.
+    pub fn omit_assets(mut self) -> Self {
+        self.omit_assets = true;
+        self
+    }
+}
+
+/// Writes one element per line, indented by its depth.
+struct Html<'a> {
+    out: String,
+    depth: usize,
+    options: &'a RenderOptions,
+}
+
+impl<'a> Html<'a> {
+    fn line(&mut self) {
+        for _ in 0..self.depth {
+            self.out.push_str("  ");
+        }
+    }
+
+    /// Writes a start tag, or with `text`, a whole element with escaped text
+    /// content. Attributes that are `None` are left out.
+    fn tag(&mut self, name: &str, attributes: &[(&str, Option<&str>)], text: Option<&str>) {
+        self.line();
+        self.out.push('<');
+        self.out.push_str(name);
+        for (attribute, value) in attributes {
+            if let Some(value) = value {
+                self.out.push(' ');
+                self.out.push_str(attribute);
+                // boolean attributes like `required` are written bare
+                if !value.is_empty() {
+                    self.out.push_str("=\"");
+                    escape_markup(value, &mut self.out);
+                    self.out.push('"');
+                }
+            }
+        }
+        match text {
+            Some(text) if VOID_ELEMENTS.contains(&name) => {
+                unreachable!("void element {} with text {}", name, text)
+            }
+            Some(text) => {
+                self.out.push('>');
+                escape_markup(text, &mut self.out);
+                self.out.push_str("</");
+                self.out.push_str(name);
+                self.out.push_str(">\n");
+            }
+            None if VOID_ELEMENTS.contains(&name) => self.out.push_str("/>\n"),
+            None => {
+                self.out.push_str(">\n");
+                self.depth += 1;
+            }
+        }
+    }
+
+    fn end(&mut self, name: &str) {
+        self.depth -= 1;
+        self.line();
+        self.out.push_str("</");
+        self.out.push_str(name);
+        self.out.push_str(">\n");
+    }
+
+    /// Instructions are markup already, and are written as they are.
+    fn instructions(&mut self, instructions: &Option<String>) {
+        if let Some(instructions) = instructions {
+            self.line();
+            self.out.push_str("<div class=\"instructions\">");
+            self.out.push_str(instructions);
+            self.out.push_str("</div>\n");
+        }
+    }
+
+    /// Where a linked asset is loaded from, or `None` if it is inline.
+    fn url(&self, asset: &Asset, extension: &str) -> Option<String> {
+        match asset {
+            Asset::Inline(_) => None,
+            Asset::Extracted(extracted) => Some(match &self.options.asset_url {
+                Some(asset_url) => format!("{}{}.{}", asset_url, extracted.sha256, extension),
+                None => extracted.path.clone(),
+            }),
+            Asset::External(external) => Some(external.url.clone()),
+        }
+    }
+
+    /// Embeds an inline asset as it is.
+    fn inline(&mut self, name: &str, asset: &Asset) {
+        if let Asset::Inline(content) = asset {
+            self.line();
+            self.out.push('<');
+            self.out.push_str(name);
+            self.out.push('>');
+            self.out.push_str(content);
+            self.out.push_str("</");
+            self.out.push_str(name);
+            self.out.push_str(">\n");
+        }
+    }
+
+    fn stylesheet(&mut self, stylesheet: &Asset) {
+        match self.url(stylesheet, "css") {
+            Some(url) => self.tag(
+                "link",
+                &[("rel", Some("stylesheet")), ("href", Some(&url))],
+                None,
+            ),
+            None => self.inline("style", stylesheet),
+        }
+    }
+
+    fn script(&mut self, script: &Script) {
+        match self.url(&script.asset, "js") {
+            Some(url) => {
+                let flag = |set: bool| if set { Some("") } else { None };
+                let attributes = [
+                    ("src", Some(url.as_str())),
+                    ("defer", flag(script.defer)),
+                    ("async", flag(script.is_async)),
+                ];
+                self.tag("script", &attributes, Some(""));
+            }
+            None => self.inline("script", &script.asset),
+        }
+    }
+
+    fn field(&mut self, section: &FormSection, required: bool, field: &FormField) {
+        let id = if section.name.is_empty() {
+            field.name.clone()
+        } else {
+            format!("{}-{}", section.name, field.name)
+        };
+        let data = data_attributes(&field.attributes);
+        let mut attributes: Vec<(&str, Option<&str>)> = vec![
+            ("id", Some(&id)),
+            ("name", Some(&field.name)),
+            ("class", field.attributes.class.as_deref()),
+        ];
+        attributes.extend(
+            data.iter()
+                .map(|(name, value)| (name.as_str(), Some(*value))),
+        );
+
+        if field.field_type == FieldType::Hidden {
+            self.tag(
+                "input",
+                &[&[("type", Some("hidden"))], &attributes[..]].concat(),
+                None,
+            );
+            return;
+        }
+
+        let label_for = match field.field_type {
+            FieldType::Grid => format!("{}-1-1", id),
+            _ => id.clone(),
+        };
+        if let Some(label) = &field.label {
+            self.tag("label", &[("for", Some(&label_for))], Some(label));
+        }
+        self.instructions(&field.instructions);
+
+        let flag = |set: bool| if set { Some("") } else { None };
+        let number = |number: Option<u16>| number.map(|number| number.to_string());
+        let length = number(field.length);
+        let minlength = number(field.minlength);
+        let maxlength = number(field.maxlength);
+        let states = [
+            ("readonly", flag(field.readonly)),
+            ("disabled", flag(field.disabled)),
+            ("required", flag(required)),
+        ];
+
+        match field.field_type {
+            FieldType::Select | FieldType::MultiSelect => {
+                let multiple = flag(field.field_type == FieldType::MultiSelect);
+                self.tag(
+                    "select",
+                    &[&attributes[..], &[("multiple", multiple)], &states].concat(),
+                    None,
+                );
+                for group in field.option_groups() {
+                    if group.label.is_some() {
+                        self.tag("optgroup", &[("label", group.label)], None);
+                    }
+                    for option in group.options {
+                        let data = data_attributes(&option.attributes);
+                        let mut attributes = vec![
+                            ("value", Some(option.name.as_str())),
+                            ("class", option.attributes.class.as_deref()),
+                        ];
+                        attributes.extend(
+                            data.iter()
+                                .map(|(name, value)| (name.as_str(), Some(*value))),
+                        );
+                        let label = option.label.as_deref().unwrap_or(&option.name);
+                        self.tag("option", &attributes, Some(label));
+                    }
+                    if group.label.is_some() {
+                        self.end("optgroup");
+                    }
+                }
+                self.end("select");
+            }
+            FieldType::TextArea => {
+                let specific = [
+                    ("cols", length.as_deref()),
+                    ("minlength", minlength.as_deref()),
+                    ("maxlength", maxlength.as_deref()),
+                    ("placeholder", field.placeholder.as_deref()),
+                    ("autocomplete", field.autocomplete.as_deref()),
+                ];
+                self.tag(
+                    "textarea",
+                    &[&attributes[..], &specific, &states].concat(),
+                    Some(""),
+                );
+            }
+            FieldType::Grid => self.grid(&id, field, &states),
+            field_type => {
+                let text_like = matches!(
+                    field_type,
+                    FieldType::Text
+                        | FieldType::Email
+                        | FieldType::Tel
+                        | FieldType::Url
+                        | FieldType::Password
+                );
+                let accept = match (field_type, &field.accept) {
+                    (_, Some(accept)) => Some(accept.as_str()),
+                    (FieldType::Image, None) => Some("image/*"),
+                    _ => None,
+                };
+                let input_type = match field_type {
+                    FieldType::Image => "file",
+                    field_type => field_type.as_str(),
+                };
+                let specific = [
+                    ("size", length.as_deref().filter(|_| text_like)),
+                    ("minlength", minlength.as_deref()),
+                    ("maxlength", maxlength.as_deref()),
+                    ("placeholder", field.placeholder.as_deref()),
+                    ("pattern", field.pattern.as_deref()),
+                    ("autocomplete", field.autocomplete.as_deref()),
+                    ("min", field.min.as_deref()),
+                    ("max", field.max.as_deref()),
+                    ("step", field.step.as_deref()),
+                    ("value", field.default.as_deref()),
+                    ("accept", accept),
+                    ("multiple", flag(field.multiple)),
+                ];
+                self.tag(
+                    "input",
+                    &[
+                        &[("type", Some(input_type))],
+                        &attributes[..],
+                        &specific,
+                        &states,
+                    ]
+                    .concat(),
+                    None,
+                );
+            }
+        }
+    }
+
+    /// A table with a row per row of the grid and a text input per cell,
+    /// named `<field>-<row>-<cell>` counting from 1. The cells of a row share
+    /// its width, rounded up.
+    fn grid(&mut self, id: &str, field: &FormField, states: &[(&str, Option<&str>)]) {
+        self.tag(
+            "table",
+            &[("class", field.attributes.class.as_deref())],
+            None,
+        );
+        for (r, width) in field.rows.iter().enumerate() {
+            let cells = match field.columns[..] {
+                [cells] => cells,
+                ref columns => columns.get(r).copied().unwrap_or(1),
+            }
+            .max(1);
+            let size = (width / cells + u16::from(width % cells != 0)).to_string();
+            self.tag("tr", &[], None);
+            for c in 0..cells {
+                let cell = format!("{}-{}-{}", id, r + 1, c + 1);
+                let name = format!("{}-{}-{}", field.name, r + 1, c + 1);
+                let attributes = [
+                    ("type", Some("text")),
+                    ("id", Some(cell.as_str())),
+                    ("name", Some(&name)),
+                    ("size", Some(&size)),
+                ];
+                self.tag("td", &[], None);
+                self.tag("input", &[&attributes[..], states].concat(), None);
+                self.end("td");
+            }
+            self.end("tr");
+        }
+        self.end("table");
+    }
+}
+
+/// `data-*` attributes, with their prefix put back.
+fn data_attributes(attributes: &ElementAttributes) -> Vec<(String, &str)> {
+    attributes
+        .data
+        .iter()
+        .map(|(name, value)| (format!("data-{}", name), value.as_str()))
+        .collect()
+}
+
+impl Form {
+    /// The form as an HTML `form` element, with its stylesheets before and its
+    /// scripts after it.
+    pub fn to_html(&self, options: &RenderOptions) -> String {
+        let mut html = Html {
+            out: String::new(),
+            depth: 0,
+            options,
+        };
+
+        if !options.omit_assets {
+            for stylesheet in &self.stylesheets {
+                html.stylesheet(stylesheet);
+            }
+        }
+        let submit = self.submit.as_ref();
+        html.tag(
+            "form",
+            &[
+                ("lang", self.language.as_deref()),
+                ("action", submit.and_then(|submit| submit.action.as_deref())),
+                ("method", submit.map(|submit| submit.method.as_str())),
+            ],
+            None,
+        );
+        if let Some(title) = &self.title {
+            html.tag("h1", &[], Some(title));
+        }
+        html.instructions(&self.instructions);
+
+        let mut section = None;
+        for node in self.walk() {
+            match node {
+                Node::SectionStart(start) => {
+                    section = Some(start);
+                    let data = data_attributes(&start.attributes);
+                    let mut attributes = vec![
+                        (
+                            "id",
+                            Some(start.name.as_str()).filter(|name| !name.is_empty()),
+                        ),
+                        ("class", start.attributes.class.as_deref()),
+                    ];
+                    attributes.extend(
+                        data.iter()
+                            .map(|(name, value)| (name.as_str(), Some(*value))),
+                    );
+                    html.tag("section", &attributes, None);
+                    if let Some(title) = &start.title {
+                        html.tag("h2", &[], Some(title));
+                    }
+                    html.instructions(&start.instructions);
+                }
+                Node::GroupStart(start) => {
+                    let id = match section {
+                        Some(section) if !section.name.is_empty() => {
+                            format!("{}-{}", section.name, start.name)
+                        }
+                        _ => start.name.clone(),
+                    };
+                    let min_repeat = start.min_repeat.map(|min| min.to_string());
+                    let max_repeat = start.max_repeat.map(|max| max.to_string());
+                    let flag = |set: bool| if set { Some("") } else { None };
+                    let data = data_attributes(&start.attributes);
+                    let mut attributes = vec![
+                        ("id", Some(id.as_str()).filter(|_| !start.name.is_empty())),
+                        ("class", start.attributes.class.as_deref()),
+                        ("aria-label", start.label.as_deref()),
+                        ("data-repeat", flag(start.repeat)),
+                        ("data-min-repeat", min_repeat.as_deref()),
+                        ("data-max-repeat", max_repeat.as_deref()),
+                        ("disabled", flag(start.disabled)),
+                    ];
+                    attributes.extend(
+                        data.iter()
+                            .map(|(name, value)| (name.as_str(), Some(*value))),
+                    );
+                    html.tag("fieldset", &attributes, None);
+                    if let Some(title) = &start.title {
+                        html.tag("legend", &[], Some(title));
+                    }
+                    html.instructions(&start.instructions);
+                }
+                Node::Field(field) => {
+                    let required = self.requiredness(field) == Some(Requiredness::Always);
+                    html.field(section.expect("fields are in sections"), required, field)
+                }
+                Node::GroupEnd(_) => html.end("fieldset"),
+                Node::SectionEnd(_) => html.end("section"),
+            }
+        }
+        if let Some(submit) = submit {
+            let label = submit.label.as_deref().unwrap_or_default();
+            html.tag("button", &[("type", Some("submit"))], Some(label));
+        }
+        html.end("form");
+
+        if !options.omit_assets {
+            for script in &self.embedded_scripts {
+                html.script(script);
+            }
+        }
+        html.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Renders `resources/<fixture>.pug` and compares it with
+    /// `resources/rendered/<fixture>.html`.
+    fn assert_renders_as_golden(fixture: &str) {
+        let form = compile(format!("resources/{}.pug", fixture)).unwrap();
+        let golden =
+            std::fs::read_to_string(format!("resources/rendered/{}.html", fixture)).unwrap();
+        assert_eq!(form.to_html(&RenderOptions::new()), golden, "{}", fixture);
+    }
+
+    #[test]
+    fn golden_files() {
+        for fixture in &[
+            "accessors",
+            "hidden",
+            "file-accept",
+            "rows",
+            "multiple-scripts.mf",
+        ] {
+            assert_renders_as_golden(fixture);
+        }
+    }
+
+    #[test]
+    fn escapes_text() {
+        let form = compile_xml_str(
+            r#"<form>
+                <title>Fish &amp; Chips</title>
+                <section name="order">
+                    <field name="portions" type="select">
+                        <label>Portions &lt;5</label>
+                        <option name="one">1 &amp; only</option>
+                    </field>
+                </section>
+            </form>"#,
+        )
+        .unwrap();
+        let html = form.to_html(&RenderOptions::new());
+        assert!(html.contains("<h1>Fish &amp; Chips</h1>"));
+        assert!(html.contains(r#"<label for="order-portions">Portions &lt;5</label>"#));
+        assert!(html.contains(r#"<option value="one">1 &amp; only</option>"#));
+    }
+
+    #[test]
+    fn external_assets() {
+        let form = compile_xml_str(
+            r#"<form>
+                <style>form { color: red; }</style>
+                <style href="https://example.com/print.css"></style>
+                <script src="https://example.com/app.js" defer=""></script>
+                <script>start();</script>
+            </form>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            form.to_html(&RenderOptions::new()),
+            "<style>form { color: red; }</style>\n\
+             <link rel=\"stylesheet\" href=\"https://example.com/print.css\"/>\n\
+             <form>\n\
+             </form>\n\
+             <script src=\"https://example.com/app.js\" defer></script>\n\
+             <script>start();</script>\n"
+        );
+    }
+
+    #[test]
+    fn optgroups() {
+        let form = compile_xml_str(
+            r#"<form>
+                <section name="a">
+                    <field name="country" type="select">
+                        <label>Country</label>
+                        <option name="none">None</option>
+                        <optgroup label="Europe">
+                            <option name="fr">France</option>
+                        </optgroup>
+                    </field>
+                </section>
+            </form>"#,
+        )
+        .unwrap();
+        let html = form.to_html(&RenderOptions::new());
+        assert!(
+            html.contains(
+                "<option value=\"none\">None</option>\n      \
+             <optgroup label=\"Europe\">\n        \
+             <option value=\"fr\">France</option>\n      \
+             </optgroup>\n"
+            ),
+            "{}",
+            html
+        );
+    }
+
+    #[test]
+    fn required_checkboxes() {
+        let form = compile_xml_str(
+            r#"<form>
+                <section name="a">
+                    <field name="terms" type="checkbox">I accept the terms</field>
+                    <field name="news" type="checkbox" optional="">Send me news</field>
+                </section>
+            </form>"#,
+        )
+        .unwrap();
+        let html = form.to_html(&RenderOptions::new());
+        assert!(html.contains(r#"name="terms" required/>"#), "{}", html);
+        assert!(html.contains(r#"name="news"/>"#), "{}", html);
+    }
+
+    #[test]
+    fn required_in_conditional_sections() {
+        let form = compile_xml_str(
+            r#"<form>
+                <section name="a">
+                    <field name="vehicle" type="checkbox" optional="">I have a vehicle</field>
+                </section>
+                <section name="b" show-if="vehicle == true">
+                    <field name="plate" type="text">Plate</field>
+                </section>
+                <section name="c" optional="">
+                    <field name="notes" type="text">Notes</field>
+                </section>
+            </form>"#,
+        )
+        .unwrap();
+        let html = form.to_html(&RenderOptions::new());
+        assert!(html.contains(r#"name="plate"/>"#), "{}", html);
+        assert!(html.contains(r#"name="notes"/>"#), "{}", html);
+    }
+}
@@ -0,0 +1,263 @@
+//! Moving sections and fields around, e.g. for a visual editor.
+//!
+//! Fields and sections are found by name, in document order. A field is only
+//! moved within its container, i.e. its group or, outside of groups, its
+//! section, unless it is moved with [`Form::move_field_across`].
+//!
+//! With [`CompileOptions::source_order`] every section, group and field is
+//! numbered in the order it appears in the source, and moving an element
+//! keeps its number, so editors can tell a manual reordering from the
+//! source order.
+
+use super::*;
+
+/// Where to move a field or section to. `Before` and `After` name a sibling;
+/// `Index` is a position in the element's current container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Position {
+    Before(String),
+    After(String),
+    Index(usize),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MoveError {
+    UnknownField(String),
+    UnknownSection(String),
+    UnknownTarget(String),
+    /// More than one field has the name.
+    AmbiguousField(String),
+    /// The target is in another group or section, and the move was not made
+    /// with [`Form::move_field_across`].
+    OtherContainer {
+        field: String,
+        target: String,
+    },
+    IndexOutOfRange {
+        index: usize,
+        len: usize,
+    },
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self {
+            MoveError::UnknownField(name) => write!(f, "there is no field '{}'", name),
+            MoveError::UnknownSection(name) => write!(f, "there is no section '{}'", name),
+            MoveError::UnknownTarget(name) => write!(f, "there is nothing named '{}'", name),
+            MoveError::AmbiguousField(name) => {
+                write!(f, "there is more than one field named '{}'", name)
+            }
+            MoveError::OtherContainer { field, target } => write!(
+                f,
+                "field '{}' and '{}' are in different groups or sections",
+                field, target
+            ),
+            MoveError::IndexOutOfRange { index, len } => {
+                write!(f, "index {} is out of range for {} elements", index, len)
+            }
+        }
+    }
+}
+
+impl error::Error for MoveError {}
+
+/// Where a field lives: its section and its index among the section's
+/// elements, and if it is in a group, its index among the group's members.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Location {
+    section: usize,
+    element: usize,
+    member: Option<usize>,
+}
+
+impl Location {
+    fn same_container(&self, other: &Location) -> bool {
+        self.section == other.section
+            && (self.member.is_some() == other.member.is_some())
+            && (self.member.is_none() || self.element == other.element)
+    }
+}
+
+impl Form {
+    fn locate_field(&self, name: &str) -> Result<Option<Location>, MoveError> {
+        let mut found = None;
+        for (s, section) in self.sections.iter().enumerate() {
+            for (e, element) in section.elements.iter().enumerate() {
+                let locations: Vec<Location> = match element {
+                    FormElement::Field(field) if field.name == name => vec![Location {
+                        section: s,
+                        element: e,
+                        member: None,
+                    }],
+                    FormElement::Field(_) => Vec::new(),
+                    FormElement::Group(group) => group
+                        .members
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, field)| field.name == name)
+                        .map(|(m, _)| Location {
+                            section: s,
+                            element: e,
+                            member: Some(m),
+                        })
+                        .collect(),
+                };
+                for location in locations {
+                    if found.replace(location).is_some() {
+                        return Err(MoveError::AmbiguousField(String::from(name)));
+                    }
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    fn remove_field(&mut self, location: Location) -> FormField {
+        let elements = &mut self.sections[location.section].elements;
+        match (location.member, &mut elements[location.element]) {
+            (Some(member), FormElement::Group(group)) => group.members.remove(member),
+            (None, FormElement::Field(_)) => match elements.remove(location.element) {
+                FormElement::Field(field) => field,
+                FormElement::Group(_) => unreachable!(),
+            },
+            _ => unreachable!("locations point at fields"),
+        }
+    }
+
+    fn insert_field(&mut self, location: Location, field: FormField) {
+        let elements = &mut self.sections[location.section].elements;
+        match (location.member, location.element) {
+            (Some(member), element) => match &mut elements[element] {
+                FormElement::Group(group) => group.members.insert(member, field),
+                FormElement::Field(_) => unreachable!("locations point at fields"),
+            },
+            (None, element) => elements.insert(element, FormElement::Field(field)),
+        }
+    }
+
+    fn container_len(&self, location: Location) -> usize {
+        let elements = &self.sections[location.section].elements;
+        match (location.member, &elements[location.element]) {
+            (Some(_), FormElement::Group(group)) => group.members.len(),
+            _ => elements.len(),
+        }
+    }
+
+    fn move_field_to(&mut self, name: &str, to: Position, across: bool) -> Result<(), MoveError> {
+        let from = self
+            .locate_field(name)?
+            .ok_or_else(|| MoveError::UnknownField(String::from(name)))?;
+
+        match &to {
+            Position::Before(target) | Position::After(target) => {
+                let location = self
+                    .locate_field(target)?
+                    .ok_or_else(|| MoveError::UnknownTarget(target.clone()))?;
+                if location == from {
+                    return Ok(());
+                }
+                if !across && !from.same_container(&location) {
+                    return Err(MoveError::OtherContainer {
+                        field: String::from(name),
+                        target: target.clone(),
+                    });
+                }
+            }
+            Position::Index(index) => {
+                let len = self.container_len(from);
+                if *index >= len {
+                    return Err(MoveError::IndexOutOfRange { index: *index, len });
+                }
+            }
+        }
+
+        let field = self.remove_field(from);
+        // the target is looked up again, since removing the field may have
+        // shifted it
+        let to = match &to {
+            Position::Index(index) => Location {
+                member: from.member.map(|_| *index),
+                element: from.member.map_or(*index, |_| from.element),
+                ..from
+            },
+            Position::Before(target) => self.locate_field(target)?.unwrap(),
+            Position::After(target) => {
+                let mut location = self.locate_field(target)?.unwrap();
+                match location.member {
+                    Some(ref mut member) => *member += 1,
+                    None => location.element += 1,
+                }
+                location
+            }
+        };
+        self.insert_field(to, field);
+        Ok(())
+    }
+
+    /// Moves a field within its group or section.
+    pub fn move_field(&mut self, field: &str, to: Position) -> Result<(), MoveError> {
+        self.move_field_to(field, to, false)
+    }
+
+    /// Like [`Form::move_field`], but also moves a field into another group
+    /// or section, or in or out of a group.
+    pub fn move_field_across(&mut self, field: &str, to: Position) -> Result<(), MoveError> {
+        self.move_field_to(field, to, true)
+    }
+
+    pub fn move_section(&mut self, section: &str, to: Position) -> Result<(), MoveError> {
+        let find = |form: &Form, name: &str| {
+            form.sections
+                .iter()
+                .position(|section| section.name == name)
+        };
+        let from =
+            find(self, section).ok_or_else(|| MoveError::UnknownSection(String::from(section)))?;
+        match &to {
+            Position::Index(index) if *index >= self.sections.len() => {
+                return Err(MoveError::IndexOutOfRange {
+                    index: *index,
+                    len: self.sections.len(),
+                })
+            }
+            Position::Before(target) | Position::After(target) => {
+                if find(self, target).is_none() {
+                    return Err(MoveError::UnknownTarget(target.clone()));
+                }
+                if target == section {
+                    return Ok(());
+                }
+            }
+            Position::Index(_) => (),
+        }
+
+        let moved = self.sections.remove(from);
+        let index = match &to {
+            Position::Index(index) => *index,
+            Position::Before(target) => find(self, target).unwrap(),
+            Position::After(target) => find(self, target).unwrap() + 1,
+        };
+        self.sections.insert(index, moved);
+        Ok(())
+    }
+
+    /// Numbers sections, groups and fields in document order.
+    pub(crate) fn number_source_order(&mut self) {
+        let mut order = 0..;
+        for section in &mut self.sections {
+            section.order = order.next();
+            for element in &mut section.elements {
+                match element {
+                    FormElement::Field(field) => field.order = order.next(),
+                    FormElement::Group(group) => {
+                        group.order = order.next();
+                        for field in &mut group.members {
+                            field.order = order.next();
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
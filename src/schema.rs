@@ -0,0 +1,425 @@
+//! A JSON Schema for compiled forms, i.e. for the output of
+//! `compile_to_json_str`, not for submissions to a form.
+//!
+//! The schema is written by hand and has to be kept in step with the serde
+//! representation of the model whenever a type changes. The tests validate
+//! every compiled fixture against it to catch drift.
+
+use super::*;
+use serde_json::{json, Map, Value};
+
+/// A string or null, as serde writes `Option<String>`.
+fn optional_string() -> Value {
+    json!({ "type": ["string", "null"] })
+}
+
+fn optional(schema: Value) -> Value {
+    json!({ "oneOf": [{ "type": "null" }, schema] })
+}
+
+fn integer(maximum: u64) -> Value {
+    json!({ "type": "integer", "minimum": 0, "maximum": maximum })
+}
+
+fn reference(definition: &str) -> Value {
+    json!({ "$ref": format!("#/definitions/{}", definition) })
+}
+
+fn array(items: Value) -> Value {
+    json!({ "type": "array", "items": items })
+}
+
+fn string_enum(variants: &[&str]) -> Value {
+    json!({ "type": "string", "enum": variants })
+}
+
+/// A struct; serde always writes every field, so all of them are required.
+fn object(properties: Value) -> Value {
+    let required: Vec<String> = properties
+        .as_object()
+        .map(|properties| properties.keys().cloned().collect())
+        .unwrap_or_default();
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+/// An externally tagged enum variant, e.g. `{ "Field": { ... } }`.
+fn tagged(tag: &str, schema: Value) -> Value {
+    let mut properties = Map::new();
+    properties.insert(String::from(tag), schema);
+    object(Value::Object(properties))
+}
+
+/// The JSON Schema (draft 7) of a compiled form.
+pub fn output_schema() -> Value {
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "Form",
+        "definitions": {
+            "Asset": {
+                "oneOf": [
+                    { "type": "string" },
+                    object(json!({
+                        "path": { "type": "string" },
+                        "sha256": { "type": "string" },
+                        "size": integer(u64::MAX),
+                    })),
+                    object(json!({
+                        "url": { "type": "string" },
+                    })),
+                ]
+            },
+            "Script": object(json!({
+                "asset": reference("Asset"),
+                "defer": { "type": "boolean" },
+                "async": { "type": "boolean" },
+            })),
+            "SubmitTarget": object(json!({
+                "action": optional_string(),
+                "method": string_enum(&["get", "post"]),
+                "label": optional_string(),
+            })),
+            "ContactInfo": object(json!({
+                "email": optional_string(),
+                "phone": optional_string(),
+                "hours": optional_string(),
+                "note": optional_string(),
+            })),
+            "Section": object(json!({
+                "name": { "type": "string" },
+                "title": optional_string(),
+                "instructions": optional_string(),
+                "elements": array(reference("Element")),
+                "attributes": reference("ElementAttributes"),
+                "order": optional(integer(u32::MAX.into())),
+                "display-order": optional(integer(u16::MAX.into())),
+            })),
+            "ElementAttributes": object(json!({
+                "requires": optional_string(),
+                "requires-conditions": array(reference("Condition")),
+                "optional": { "type": "boolean" },
+                "required": { "type": "boolean" },
+                "optional-if": optional_string(),
+                "optional-if-conditions": array(reference("Condition")),
+                "class": optional_string(),
+                "show-if": optional(reference("Condition")),
+                "data": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string" },
+                },
+                "audience": array(json!({ "type": "string" })),
+            })),
+            "Condition": object(json!({
+                "field": { "type": "string" },
+                "operator": string_enum(&[
+                    "equals",
+                    "not-equals",
+                    "contains",
+                    "empty",
+                    "not-empty",
+                    "selected",
+                ]),
+                "value": optional_string(),
+            })),
+            "Element": {
+                "oneOf": [
+                    tagged("Group", reference("Group")),
+                    tagged("Field", reference("Field")),
+                ]
+            },
+            "Group": object(json!({
+                "name": { "type": "string" },
+                "title": optional_string(),
+                "label": optional_string(),
+                "instructions": optional_string(),
+                "members": array(reference("Field")),
+                "group-type": string_enum(&["row", "subsection"]),
+                "attributes": reference("ElementAttributes"),
+                "repeat": { "type": "boolean" },
+                "min-repeat": optional(integer(u16::MAX.into())),
+                "max-repeat": optional(integer(u16::MAX.into())),
+                "disabled": { "type": "boolean" },
+                "order": optional(integer(u32::MAX.into())),
+            })),
+            "Field": object(json!({
+                "name": { "type": "string" },
+                "field-type": string_enum(&[
+                    "text",
+                    "number",
+                    "range",
+                    "checkbox",
+                    "file",
+                    "image",
+                    "select",
+                    "multi-select",
+                    "textarea",
+                    "date",
+                    "time",
+                    "datetime-local",
+                    "month",
+                    "week",
+                    "email",
+                    "tel",
+                    "url",
+                    "password",
+                    "color",
+                    "grid",
+                    "hidden",
+                ]),
+                "instructions": optional_string(),
+                "label": optional_string(),
+                "length": optional(integer(u16::MAX.into())),
+                "minlength": optional(integer(u16::MAX.into())),
+                "maxlength": optional(integer(u16::MAX.into())),
+                "placeholder": optional_string(),
+                "pattern": optional_string(),
+                "autocomplete": optional_string(),
+                "min": optional_string(),
+                "max": optional_string(),
+                "step": optional_string(),
+                "default": optional_string(),
+                "attributes": reference("ElementAttributes"),
+                "rows": array(integer(u16::MAX.into())),
+                "columns": array(integer(u16::MAX.into())),
+                "options": array(reference("Option")),
+                "accept": optional_string(),
+                "multiple": { "type": "boolean" },
+                "min-selections": optional(integer(u16::MAX.into())),
+                "max-selections": optional(integer(u16::MAX.into())),
+                "min-words": optional(integer(u16::MAX.into())),
+                "max-words": optional(integer(u16::MAX.into())),
+                "readonly": { "type": "boolean" },
+                "disabled": { "type": "boolean" },
+                "order": optional(integer(u32::MAX.into())),
+            })),
+            "Option": object(json!({
+                "name": { "type": "string" },
+                "label": optional_string(),
+                "optgroup": optional_string(),
+                "attributes": reference("ElementAttributes"),
+            })),
+        },
+        "type": "object",
+        "properties": {
+            "title": optional_string(),
+            "unlisted": { "type": "boolean" },
+            "optional-by-default": { "type": "boolean" },
+            "description": optional_string(),
+            "meta-description": optional_string(),
+            "dir-description": optional_string(),
+            "embedded-scripts": array(reference("Script")),
+            "category": optional_string(),
+            "instructions": optional_string(),
+            "link": optional_string(),
+            "links": array(object(json!({
+                "href": { "type": "string" },
+                "label": optional_string(),
+            }))),
+            "index": optional(integer(u32::MAX.into())),
+            "stylesheets": array(reference("Asset")),
+            "sections": array(reference("Section")),
+            "language": optional_string(),
+            "keywords": optional_string(),
+            "keyword-list": array(json!({ "type": "string" })),
+            "version": optional_string(),
+            "author": optional_string(),
+            "updated": optional_string(),
+            "contact": optional(reference("ContactInfo")),
+            "submit": optional(reference("SubmitTarget")),
+        },
+        "required": [
+            "title",
+            "unlisted",
+            "optional-by-default",
+            "description",
+            "meta-description",
+            "dir-description",
+            "embedded-scripts",
+            "category",
+            "instructions",
+            "link",
+            "links",
+            "index",
+            "stylesheets",
+            "sections",
+            "language",
+            "keywords",
+            "keyword-list",
+            "version",
+            "author",
+            "updated",
+            "contact",
+            "submit",
+        ],
+        "additionalProperties": false,
+    })
+}
+
+/// A place where a document does not match the output schema.
+#[derive(Debug)]
+pub struct SchemaViolation {
+    /// JSON pointer to the offending value.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "/"
+        } else {
+            &self.path
+        };
+        write!(f, "{}: {}", path, self.message)
+    }
+}
+
+/// Validates against the subset of JSON Schema `output_schema` uses.
+struct Validator<'a> {
+    root: &'a Value,
+    violations: Vec<SchemaViolation>,
+}
+
+impl<'a> Validator<'a> {
+    fn violation(&mut self, path: &str, message: String) {
+        self.violations.push(SchemaViolation {
+            path: String::from(path),
+            message,
+        });
+    }
+
+    fn has_type(value: &Value, name: &str) -> bool {
+        match name {
+            "null" => value.is_null(),
+            "boolean" => value.is_boolean(),
+            "integer" => value.is_u64() || value.is_i64(),
+            "number" => value.is_number(),
+            "string" => value.is_string(),
+            "array" => value.is_array(),
+            "object" => value.is_object(),
+            _ => false,
+        }
+    }
+
+    fn check(&mut self, schema: &'a Value, value: &Value, path: &str) {
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let definition = reference
+                .strip_prefix('#')
+                .and_then(|pointer| self.root.pointer(pointer));
+            match definition {
+                Some(definition) => self.check(definition, value, path),
+                None => self.violation(path, format!("unresolvable $ref {}", reference)),
+            }
+            return;
+        }
+
+        if let Some(alternatives) = schema.get("oneOf").and_then(Value::as_array) {
+            let matching = alternatives
+                .iter()
+                .filter(|alternative| {
+                    let mut validator = Validator {
+                        root: self.root,
+                        violations: Vec::new(),
+                    };
+                    validator.check(alternative, value, path);
+                    validator.violations.is_empty()
+                })
+                .count();
+            if matching != 1 {
+                self.violation(
+                    path,
+                    format!(
+                        "should match exactly one of {} alternatives, matches {}",
+                        alternatives.len(),
+                        matching
+                    ),
+                );
+            }
+            return;
+        }
+
+        if let Some(types) = schema.get("type") {
+            let types: Vec<&str> = match types {
+                Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+                types => types.as_str().into_iter().collect(),
+            };
+            if !types.iter().any(|name| Validator::has_type(value, name)) {
+                self.violation(
+                    path,
+                    format!("expected {}, got {}", types.join(" or "), value),
+                );
+                return;
+            }
+        }
+
+        if let Some(variants) = schema.get("enum").and_then(Value::as_array) {
+            if !variants.contains(value) {
+                self.violation(path, format!("{} is not one of {:?}", value, variants));
+            }
+        }
+
+        if let Some(number) = value.as_f64() {
+            if let Some(minimum) = schema.get("minimum").and_then(Value::as_f64) {
+                if number < minimum {
+                    self.violation(path, format!("{} is less than {}", number, minimum));
+                }
+            }
+            if let Some(maximum) = schema.get("maximum").and_then(Value::as_f64) {
+                if number > maximum {
+                    self.violation(path, format!("{} is greater than {}", number, maximum));
+                }
+            }
+        }
+
+        if let (Some(items), Some(elements)) = (schema.get("items"), value.as_array()) {
+            for (i, element) in elements.iter().enumerate() {
+                self.check(items, element, &format!("{}/{}", path, i));
+            }
+        }
+
+        if let Some(object) = value.as_object() {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for name in required.iter().filter_map(Value::as_str) {
+                    if !object.contains_key(name) {
+                        self.violation(path, format!("missing property {}", name));
+                    }
+                }
+            }
+            for (name, property) in object {
+                let property_path =
+                    format!("{}/{}", path, name.replace('~', "~0").replace('/', "~1"));
+                match properties.and_then(|properties| properties.get(name)) {
+                    Some(property_schema) => self.check(property_schema, property, &property_path),
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            self.violation(&property_path, String::from("unexpected property"))
+                        }
+                        Some(Value::Bool(true)) | None => (),
+                        Some(additional) => self.check(additional, property, &property_path),
+                    },
+                }
+            }
+        }
+    }
+}
+
+/// Checks a compiled form, e.g. one read back from `compile_to_json_str`,
+/// against `output_schema`.
+pub fn validate_output(json: &Value) -> Result<(), Vec<SchemaViolation>> {
+    let schema = output_schema();
+    let mut validator = Validator {
+        root: &schema,
+        violations: Vec::new(),
+    };
+    validator.check(&schema, json, "");
+    if validator.violations.is_empty() {
+        Ok(())
+    } else {
+        Err(validator.violations)
+    }
+}
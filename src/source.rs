@@ -0,0 +1,438 @@
+//! Writing a compiled form back out as XML, e.g. after editing its JSON, so
+//! that the source stays the source of truth.
+//!
+//! The XML uses the same elements and attributes as templates, with
+//! attributes in a fixed order, so writing out a form, compiling the XML and
+//! writing it out again gives the same text. Options loaded with
+//! `options-from` are written inline. Extracted assets, and the numbering of
+//! [`CompileOptions::source_order`], are not written.
+
+use super::*;
+
+impl Condition {
+    /// The condition as written in `show-if`.
+    fn to_expression(&self) -> String {
+        let literal = |value: &Option<String>| {
+            let value = value.as_deref().unwrap_or_default();
+            if value.contains('"') {
+                format!("'{}'", value)
+            } else {
+                format!("\"{}\"", value)
+            }
+        };
+        match self.operator {
+            Operator::Equals => format!("{} == {}", self.field, literal(&self.value)),
+            Operator::NotEquals => format!("{} != {}", self.field, literal(&self.value)),
+            Operator::Contains => format!("{} contains {}", self.field, literal(&self.value)),
+            Operator::Empty => format!("{} empty", self.field),
+            // only references in `requires` and `optional-if` parse to these
+            Operator::NotEmpty => format!("{} != \"\"", self.field),
+            Operator::Selected => format!("{} == {}", self.field, literal(&self.value)),
+        }
+    }
+}
+
+/// Writes one element per line, indented by its depth.
+#[derive(Default)]
+struct Source {
+    out: String,
+    depth: usize,
+}
+
+impl Source {
+    fn line(&mut self) {
+        for _ in 0..self.depth {
+            self.out.push_str("  ");
+        }
+    }
+
+    fn start_tag(&mut self, name: &str, attributes: &[(&str, Option<&str>)]) {
+        self.out.push('<');
+        self.out.push_str(name);
+        for (attribute, value) in attributes {
+            if let Some(value) = value {
+                self.out.push(' ');
+                self.out.push_str(attribute);
+                self.out.push_str("=\"");
+                escape_markup(value, &mut self.out);
+                self.out.push('"');
+            }
+        }
+    }
+
+    /// Writes an element that holds other elements, which is ended by `end`.
+    fn start(&mut self, name: &str, attributes: &[(&str, Option<&str>)]) {
+        self.line();
+        self.start_tag(name, attributes);
+        self.out.push_str(">\n");
+        self.depth += 1;
+    }
+
+    fn end(&mut self, name: &str) {
+        self.depth -= 1;
+        self.line();
+        self.out.push_str("</");
+        self.out.push_str(name);
+        self.out.push_str(">\n");
+    }
+
+    /// Writes an element holding `text`, escaped, or with `markup`, as it
+    /// is.
+    fn text(&mut self, name: &str, attributes: &[(&str, Option<&str>)], text: &str, markup: bool) {
+        self.line();
+        self.start_tag(name, attributes);
+        self.out.push('>');
+        if markup {
+            self.out.push_str(text);
+        } else {
+            escape_markup(text, &mut self.out);
+        }
+        self.out.push_str("</");
+        self.out.push_str(name);
+        self.out.push_str(">\n");
+    }
+
+    /// Writes an inline or external `script` or `style`. Extracted ones are
+    /// left out.
+    fn asset(
+        &mut self,
+        name: &str,
+        url_attribute: &str,
+        asset: &Asset,
+        flags: &[(&str, Option<&str>)],
+    ) {
+        match asset {
+            Asset::Inline(content) => self.text(name, flags, content, false),
+            Asset::External(external) => {
+                let mut attributes = vec![(url_attribute, Some(external.url.as_str()))];
+                attributes.extend_from_slice(flags);
+                self.line();
+                self.start_tag(name, &attributes);
+                self.out.push_str("/>\n");
+            }
+            Asset::Extracted(_) => (),
+        }
+    }
+
+    fn optional_text(&mut self, name: &str, text: &Option<String>) {
+        if let Some(text) = text {
+            self.text(name, &[], text, false);
+        }
+    }
+
+    fn instructions(&mut self, instructions: &Option<String>) {
+        if let Some(instructions) = instructions {
+            self.text("instructions", &[], instructions, true);
+        }
+    }
+
+    fn section(&mut self, section: &FormSection) {
+        let display_order = section.display_order.map(|order| order.to_string());
+        let attributes = section.attributes.to_attributes();
+        let mut all = vec![
+            ("name", Some(section.name.as_str())),
+            ("display-order", display_order.as_deref()),
+        ];
+        all.extend(as_refs(&attributes));
+        self.start("section", &all);
+        self.optional_text("title", &section.title);
+        self.instructions(&section.instructions);
+        for element in &section.elements {
+            match element {
+                FormElement::Field(field) => self.field(field),
+                FormElement::Group(group) => self.group(group),
+            }
+        }
+        self.end("section");
+    }
+
+    fn group(&mut self, group: &FormGroup) {
+        let min_repeat = group.min_repeat.map(|min| min.to_string());
+        let max_repeat = group.max_repeat.map(|max| max.to_string());
+        let attributes = group.attributes.to_attributes();
+        let mut all = vec![
+            (
+                "name",
+                Some(group.name.as_str()).filter(|name| !name.is_empty()),
+            ),
+            ("type", Some(group.group_type.as_str())),
+            ("repeat", flag("repeat", group.repeat)),
+            ("min-repeat", min_repeat.as_deref()),
+            ("max-repeat", max_repeat.as_deref()),
+            ("disabled", flag("disabled", group.disabled)),
+        ];
+        all.extend(as_refs(&attributes));
+        self.start("group", &all);
+        self.optional_text("title", &group.title);
+        self.optional_text("label", &group.label);
+        self.instructions(&group.instructions);
+        for field in &group.members {
+            self.field(field);
+        }
+        self.end("group");
+    }
+
+    fn field(&mut self, field: &FormField) {
+        let dimensions = |dimensions: &[u16]| {
+            if dimensions.is_empty() {
+                None
+            } else {
+                let dimensions: Vec<String> = dimensions.iter().map(u16::to_string).collect();
+                Some(dimensions.join(" "))
+            }
+        };
+        let number = |number: Option<u16>| number.map(|number| number.to_string());
+        let length = number(field.length);
+        let minlength = number(field.minlength);
+        let maxlength = number(field.maxlength);
+        let rows = dimensions(&field.rows);
+        let columns = dimensions(&field.columns);
+        let min_selections = number(field.min_selections);
+        let max_selections = number(field.max_selections);
+        let min_words = number(field.min_words);
+        let max_words = number(field.max_words);
+        let attributes = field.attributes.to_attributes();
+        let mut all = vec![
+            ("name", Some(field.name.as_str())),
+            ("type", Some(field.field_type.as_str())),
+            ("length", length.as_deref()),
+            ("minlength", minlength.as_deref()),
+            ("maxlength", maxlength.as_deref()),
+            ("placeholder", field.placeholder.as_deref()),
+            ("pattern", field.pattern.as_deref()),
+            ("autocomplete", field.autocomplete.as_deref()),
+            ("min", field.min.as_deref()),
+            ("max", field.max.as_deref()),
+            ("step", field.step.as_deref()),
+            ("default", field.default.as_deref()),
+            ("rows", rows.as_deref()),
+            ("columns", columns.as_deref()),
+            ("accept", field.accept.as_deref()),
+            ("multiple", flag("multiple", field.multiple)),
+            ("min-selections", min_selections.as_deref()),
+            ("max-selections", max_selections.as_deref()),
+            ("min-words", min_words.as_deref()),
+            ("max-words", max_words.as_deref()),
+            ("readonly", flag("readonly", field.readonly)),
+            ("disabled", flag("disabled", field.disabled)),
+        ];
+        all.extend(as_refs(&attributes));
+        self.start("field", &all);
+        self.optional_text("label", &field.label);
+        self.instructions(&field.instructions);
+        for group in field.option_groups() {
+            if group.label.is_some() {
+                self.start("optgroup", &[("label", group.label)]);
+            }
+            for option in group.options {
+                let attributes = option.attributes.to_attributes();
+                let mut all = vec![("name", Some(option.name.as_str()))];
+                all.extend(as_refs(&attributes));
+                let label = option.label.as_deref().unwrap_or_default();
+                self.text("option", &all, label, false);
+            }
+            if group.label.is_some() {
+                self.end("optgroup");
+            }
+        }
+        self.end("field");
+    }
+}
+
+/// A boolean attribute, written as `name="name"`.
+fn flag(name: &str, set: bool) -> Option<&str> {
+    if set {
+        Some(name)
+    } else {
+        None
+    }
+}
+
+fn as_refs(attributes: &[(String, String)]) -> impl Iterator<Item = (&str, Option<&str>)> {
+    attributes
+        .iter()
+        .map(|(name, value)| (name.as_str(), Some(value.as_str())))
+}
+
+impl ElementAttributes {
+    /// The attributes every element takes, as written in a template.
+    fn to_attributes(&self) -> Vec<(String, String)> {
+        let mut attributes = Vec::new();
+        if let Some(requires) = &self.requires {
+            attributes.push((String::from("requires"), requires.clone()));
+        }
+        if self.required {
+            attributes.push((String::from("required"), String::from("required")));
+        } else if self.optional {
+            attributes.push((String::from("optional"), String::from("optional")));
+        }
+        if let Some(optional_if) = &self.optional_if {
+            attributes.push((String::from("optional-if"), optional_if.clone()));
+        }
+        if let Some(show_if) = &self.show_if {
+            attributes.push((String::from("show-if"), show_if.to_expression()));
+        }
+        if let Some(class) = &self.class {
+            attributes.push((String::from("class"), class.clone()));
+        }
+        if !self.audience.is_empty() {
+            attributes.push((String::from("audience"), self.audience.join(" ")));
+        }
+        for (name, value) in &self.data {
+            attributes.push((format!("data-{}", name), value.clone()));
+        }
+        attributes
+    }
+}
+
+impl Form {
+    /// The form as XML that compiles back to the same form, see the
+    /// [module documentation](self).
+    ///
+    /// ```
+    /// let form = mouse_forms::compile_xml_str(
+    ///     r#"<form><section name="applicant"><field name="surname" type="text">Surname</field></section></form>"#,
+    /// )
+    /// .unwrap();
+    /// let xml = form.to_xml();
+    /// assert!(xml.contains(r#"<field name="surname" type="text">"#));
+    /// assert!(xml.contains("<label>Surname</label>"));
+    /// ```
+    pub fn to_xml(&self) -> String {
+        let mut source = Source::default();
+        source.start("form", &[]);
+        source.optional_text("title", &self.title);
+        source.optional_text("description", &self.description);
+        source.optional_text("meta-description", &self.meta_description);
+        source.optional_text("dir-description", &self.dir_description);
+        for link in &self.links {
+            match &link.label {
+                Some(label) => source.text("link", &[("href", Some(&link.href))], label, false),
+                None => source.text("link", &[], &link.href, false),
+            }
+        }
+        source.optional_text("language", &self.language);
+        source.optional_text("keywords", &self.keywords);
+        source.optional_text("category", &self.category);
+        source.optional_text("index", &self.index.map(|index| index.to_string()));
+        source.optional_text("version", &self.version);
+        source.optional_text("author", &self.author);
+        source.optional_text("updated", &self.updated);
+        if self.unlisted {
+            source.line();
+            source.out.push_str("<unlisted/>\n");
+        }
+        if self.optional_by_default {
+            source.line();
+            source.out.push_str("<optional-by-default/>\n");
+        }
+        if let Some(contact) = &self.contact {
+            source.start("contact", &[]);
+            source.optional_text("email", &contact.email);
+            source.optional_text("phone", &contact.phone);
+            source.optional_text("hours", &contact.hours);
+            source.optional_text("note", &contact.note);
+            source.end("contact");
+        }
+        if let Some(submit) = &self.submit {
+            let attributes = [
+                ("action", submit.action.as_deref()),
+                ("method", Some(submit.method.as_str())),
+            ];
+            source.text(
+                "submit",
+                &attributes,
+                submit.label.as_deref().unwrap_or_default(),
+                false,
+            );
+        }
+        for stylesheet in &self.stylesheets {
+            source.asset("style", "href", stylesheet, &[]);
+        }
+        for script in &self.embedded_scripts {
+            let flags = [
+                ("defer", flag("defer", script.defer)),
+                ("async", flag("async", script.is_async)),
+            ];
+            source.asset("script", "src", &script.asset, &flags);
+        }
+        source.instructions(&self.instructions);
+        for section in &self.sections {
+            source.section(section);
+        }
+        source.end("form");
+        source.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for fixture in &[
+            "resources/accessors.pug",
+            "resources/autocomplete.pug",
+            "resources/show-if.pug",
+            "resources/repeat.pug",
+            "resources/options-from.pug",
+            "resources/multiple-scripts.mf.pug",
+        ] {
+            let form = compile(fixture).unwrap();
+            let reparsed = compile_xml_str(&form.to_xml()).unwrap();
+            assert_eq!(
+                serde_json::to_string(&reparsed).unwrap(),
+                serde_json::to_string(&form).unwrap(),
+                "{}",
+                fixture
+            );
+        }
+    }
+
+    #[test]
+    fn writes_the_same_xml_again() {
+        let xml = r#"<form>
+  <title>Fish &amp; Chips</title>
+  <description>Order online</description>
+  <meta-description>Order online</meta-description>
+  <dir-description>Order online</dir-description>
+  <link>/menu.pdf</link>
+  <link href="/allergens.pdf">Allergens</link>
+  <language>en</language>
+  <index>3</index>
+  <unlisted/>
+  <contact>
+    <email>orders@example.com</email>
+  </contact>
+  <submit action="/order" method="post">Order</submit>
+  <style>form { color: red; }</style>
+  <style href="https://example.com/print.css"/>
+  <script>if (1 &lt; 2) {}</script>
+  <script src="https://example.com/app.js" defer="defer" async="async"/>
+  <instructions>See <a href="https://example.com/?a=1&amp;b=2">the menu</a>.<br/></instructions>
+  <section name="order" display-order="2" class="wide" data-step="1">
+    <title>Order</title>
+    <field name="dish" type="select" audience="staff guests">
+      <label>Dish</label>
+      <option name="fish">Fish</option>
+      <optgroup label="Sides">
+        <option name="chips" show-if="dish != &quot;fish&quot;">Chips</option>
+      </optgroup>
+    </field>
+    <group name="extras" type="subsection" repeat="repeat" max-repeat="3" optional="optional">
+      <title>Extras</title>
+      <field name="extra" type="text" length="20" pattern="[a-z ]+" requires="dish" optional-if="dish.chips">
+        <label>Extra</label>
+      </field>
+    </group>
+    <field name="notes" type="textarea" max-words="50" show-if="dish empty">
+      <label>Notes</label>
+    </field>
+  </section>
+</form>
+"#;
+        let form = compile_xml_str(xml).unwrap();
+        assert_eq!(form.to_xml(), xml);
+    }
+}
@@ -0,0 +1,115 @@
+//! Structural comparison of forms.
+//!
+//! Two forms are structurally equal when they would accept the same
+//! submissions, regardless of how they are presented. The structure of a form
+//! consists of, in document order:
+//!
+//! - section names and their constraints
+//! - group names, group types, repeat bounds, `disabled` and their
+//!   constraints
+//! - field names, field types, `length`, `pattern`, `min`, `max`, `step`,
+//!   `rows`, `columns`, `accept`, `multiple`, `disabled`, `readonly`,
+//!   selection bounds, character and word limits and their constraints
+//! - option names and their constraints
+//!
+//! where the constraints of an element are `requires`, `optional`,
+//! `optional-if` and `show-if`.
+//!
+//! Everything else is presentation and is ignored: titles, labels,
+//! placeholders, instructions, descriptions, keywords, categories, links,
+//! classes, scripts, stylesheets, language, index and `unlisted`.
+
+use super::*;
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+
+impl ElementAttributes {
+    fn write_structure(&self, out: &mut String) {
+        writeln!(
+            out,
+            "  requires={:?} optional={} optional-if={:?} show-if={:?}",
+            self.requires, self.optional, self.optional_if, self.show_if
+        )
+        .unwrap();
+    }
+}
+
+impl FormField {
+    fn write_structure(&self, out: &mut String) {
+        writeln!(
+            out,
+            "field {:?} {:?} length={:?} minlength={:?} maxlength={:?} pattern={:?} min={:?} max={:?} step={:?} rows={:?} columns={:?} accept={:?} multiple={} min-selections={:?} max-selections={:?} min-words={:?} max-words={:?} disabled={} readonly={}",
+            self.name,
+            self.field_type,
+            self.length,
+            self.minlength,
+            self.maxlength,
+            self.pattern,
+            self.min,
+            self.max,
+            self.step,
+            self.rows,
+            self.columns,
+            self.accept,
+            self.multiple,
+            self.min_selections,
+            self.max_selections,
+            self.min_words,
+            self.max_words,
+            self.disabled,
+            self.readonly
+        )
+        .unwrap();
+        self.attributes.write_structure(out);
+        for option in &self.options {
+            writeln!(out, "option {:?}", option.name).unwrap();
+            option.attributes.write_structure(out);
+        }
+    }
+}
+
+impl Form {
+    fn structure(&self) -> String {
+        let mut out = String::new();
+        for section in &self.sections {
+            writeln!(out, "section {:?}", section.name).unwrap();
+            section.attributes.write_structure(&mut out);
+            for element in &section.elements {
+                match element {
+                    FormElement::Field(field) => field.write_structure(&mut out),
+                    FormElement::Group(group) => {
+                        writeln!(
+                            out,
+                            "group {:?} {:?} repeat={} min-repeat={:?} max-repeat={:?} disabled={}",
+                            group.name,
+                            group.group_type,
+                            group.repeat,
+                            group.min_repeat,
+                            group.max_repeat,
+                            group.disabled
+                        )
+                        .unwrap();
+                        group.attributes.write_structure(&mut out);
+                        for field in &group.members {
+                            field.write_structure(&mut out);
+                        }
+                        writeln!(out, "end group").unwrap();
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// A hex-encoded SHA-256 hash of the structure of the form. See the module
+    /// documentation for what counts as structure.
+    pub fn structural_fingerprint(&self) -> String {
+        format!("{:x}", Sha256::digest(self.structure().as_bytes()))
+    }
+
+    /// Whether `self` and `other` have the same structure, ignoring all
+    /// presentation.
+    pub fn structurally_eq(&self, other: &Form) -> bool {
+        self.structure() == other.structure()
+    }
+}
@@ -0,0 +1,662 @@
+//! Checking what users submit against the form they filled in.
+//!
+//! A submission is a JSON object keyed by field name. The members of a
+//! repeatable group are submitted under the group's name instead, as an array
+//! with an object for each repetition.
+//!
+//! Fields only have to be filled in when they apply: their `requires`
+//! references are met, their `show-if` expression holds, and the same goes
+//! for their group and section. Fields that do not apply, and disabled
+//! fields, are not checked at all.
+
+use super::*;
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationErrorKind {
+    /// The submission is not a JSON object, or a repetition of a group is
+    /// not one.
+    NotAnObject,
+    /// A key that is not the name of a field or repeatable group.
+    Unknown,
+    /// A field that is not optional was left empty.
+    Missing,
+    NotAString,
+    NotANumber,
+    NotABoolean,
+    /// A select value that is not one of the field's options.
+    NotAnOption,
+    /// Too few or too many options of a multi-select.
+    Selections,
+    /// Too few or too many characters, see `minlength` and `maxlength`.
+    Characters,
+    /// Too few or too many words in a textarea.
+    Words,
+    /// Too few or too many repetitions of a group.
+    Repetitions,
+    /// A value that does not match the field's `pattern`; only checked with
+    /// the `regex` feature, and only for patterns the regex crate can parse.
+    Pattern,
+    /// A number below a number or range's `min` or above its `max`.
+    OutOfRange,
+    /// A color that is not written as a hex color like `#ff0000`.
+    NotAColor,
+}
+
+/// A problem with a submitted value. `field` is the name of the field, or
+/// for a member of a repeatable group, e.g. `member[1].member-name`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    pub field: String,
+    pub kind: ValidationErrorKind,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl error::Error for ValidationError {}
+
+/// Whether a value counts as not filled in. An unchecked checkbox does too,
+/// so a checkbox that is not optional has to be checked.
+fn is_empty(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) | Some(Value::Bool(false)) => true,
+        Some(Value::String(s)) => s.trim().is_empty(),
+        Some(Value::Array(values)) => values.is_empty(),
+        Some(_) => false,
+    }
+}
+
+/// A scalar value as it would be written in a condition.
+fn as_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// The values a submission is checked against: a repetition of a group, if
+/// any, and the top level of the submission.
+struct Scope<'a> {
+    entry: Option<&'a Map<String, Value>>,
+    data: &'a Map<String, Value>,
+}
+
+impl<'a> Scope<'a> {
+    fn get(&self, name: &str) -> Option<&'a Value> {
+        self.entry
+            .and_then(|entry| entry.get(name))
+            .or_else(|| self.data.get(name))
+    }
+
+    fn holds(&self, condition: &Condition) -> bool {
+        let value = self.get(&condition.field);
+        let expected = condition.value.as_deref();
+        let equals = || value.and_then(as_text).as_deref() == expected;
+        match condition.operator {
+            Operator::Empty => is_empty(value),
+            Operator::NotEmpty => !is_empty(value),
+            Operator::Equals => equals(),
+            Operator::NotEquals => !equals(),
+            Operator::Selected => match value {
+                Some(Value::Array(values)) => values.iter().any(|value| value.as_str() == expected),
+                Some(value) => value.as_str() == expected,
+                None => false,
+            },
+            Operator::Contains => match (value, expected) {
+                (Some(Value::Array(values)), Some(expected)) => {
+                    values.iter().any(|value| value.as_str() == Some(expected))
+                }
+                (Some(Value::String(s)), Some(expected)) => s.contains(expected),
+                _ => false,
+            },
+        }
+    }
+
+    fn applies(&self, attributes: &ElementAttributes) -> bool {
+        attributes
+            .requires_conditions
+            .iter()
+            .all(|condition| self.holds(condition))
+            && attributes
+                .show_if
+                .as_ref()
+                .map(|condition| self.holds(condition))
+                != Some(false)
+    }
+
+    fn is_optional(&self, attributes: &ElementAttributes) -> bool {
+        attributes.optional
+            || attributes
+                .optional_if_conditions
+                .iter()
+                .any(|condition| self.holds(condition))
+    }
+}
+
+struct Checker {
+    errors: Vec<ValidationError>,
+}
+
+impl Checker {
+    fn error(&mut self, field: &str, kind: ValidationErrorKind, message: String) {
+        self.errors.push(ValidationError {
+            field: String::from(field),
+            kind,
+            message,
+        });
+    }
+
+    /// Checks a field that applies; `path` is how it is named in errors.
+    fn check_field(&mut self, field: &FormField, path: &str, optional: bool, scope: &Scope) {
+        let value = match scope.get(&field.name) {
+            value if is_empty(value) => {
+                if !optional {
+                    let missing = match field.field_type {
+                        FieldType::Checkbox => "checked",
+                        _ => "filled in",
+                    };
+                    self.error(
+                        path,
+                        ValidationErrorKind::Missing,
+                        format!("{} must be {}", path, missing),
+                    );
+                }
+                return;
+            }
+            Some(value) => value,
+            None => return,
+        };
+
+        match field.field_type {
+            FieldType::Checkbox => {
+                if !value.is_boolean() {
+                    self.error(
+                        path,
+                        ValidationErrorKind::NotABoolean,
+                        format!("{} should be true or false", path),
+                    );
+                }
+            }
+            FieldType::Number | FieldType::Range => {
+                let number = value
+                    .as_f64()
+                    .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()));
+                match number {
+                    Some(number)
+                        if field.min().map(|min| number < min) == Some(true)
+                            || field.max().map(|max| number > max) == Some(true) =>
+                    {
+                        self.error(
+                            path,
+                            ValidationErrorKind::OutOfRange,
+                            format!(
+                                "{} is {}, which is outside of {}..{}",
+                                path,
+                                number,
+                                field.min.as_deref().unwrap_or_default(),
+                                field.max.as_deref().unwrap_or_default()
+                            ),
+                        )
+                    }
+                    Some(_) => (),
+                    None => self.error(
+                        path,
+                        ValidationErrorKind::NotANumber,
+                        format!("{} should be a number, got {}", path, value),
+                    ),
+                }
+            }
+            FieldType::Color => {
+                if value.as_str().map(is_hex_color) != Some(true) {
+                    self.error(
+                        path,
+                        ValidationErrorKind::NotAColor,
+                        format!("{} should be a hex color like #ff0000, got {}", path, value),
+                    );
+                }
+            }
+            FieldType::Select => match value.as_str() {
+                Some(selected) => self.check_option(field, path, selected),
+                None => self.error(
+                    path,
+                    ValidationErrorKind::NotAString,
+                    format!("{} should be the name of an option", path),
+                ),
+            },
+            FieldType::MultiSelect => match value.as_array() {
+                Some(values) => {
+                    for value in values {
+                        match value.as_str() {
+                            Some(selected) => self.check_option(field, path, selected),
+                            None => self.error(
+                                path,
+                                ValidationErrorKind::NotAString,
+                                format!("{} should only hold names of options", path),
+                            ),
+                        }
+                    }
+                    let count = values.len();
+                    let too_few = field.min_selections.map(|min| count < min.into());
+                    let too_many = field.max_selections.map(|max| count > max.into());
+                    if too_few == Some(true) || too_many == Some(true) {
+                        self.error(
+                            path,
+                            ValidationErrorKind::Selections,
+                            format!(
+                                "{} has {} options selected, which is outside of {}..{}",
+                                path,
+                                count,
+                                field.min_selections.unwrap_or(0),
+                                field
+                                    .max_selections
+                                    .map(|max| max.to_string())
+                                    .unwrap_or_default()
+                            ),
+                        );
+                    }
+                }
+                None => self.error(
+                    path,
+                    ValidationErrorKind::NotAString,
+                    format!("{} should be a list of option names", path),
+                ),
+            },
+            // files are uploaded separately, and grids are rendered as many
+            // inputs of their own
+            FieldType::File | FieldType::Image | FieldType::Grid => (),
+            _ => match value.as_str() {
+                Some(text) => self.check_text(field, path, text),
+                None => self.error(
+                    path,
+                    ValidationErrorKind::NotAString,
+                    format!("{} should be text, got {}", path, value),
+                ),
+            },
+        }
+    }
+
+    fn check_option(&mut self, field: &FormField, path: &str, selected: &str) {
+        if !field.options.iter().any(|option| option.name == selected) {
+            self.error(
+                path,
+                ValidationErrorKind::NotAnOption,
+                format!("'{}' is not an option of {}", selected, path),
+            );
+        }
+    }
+
+    fn check_text(&mut self, field: &FormField, path: &str, text: &str) {
+        // counted like browsers do, in UTF-16 code units
+        let characters = text.encode_utf16().count();
+        let too_short = field.minlength.map(|min| characters < min.into());
+        let too_long = field.maxlength.map(|max| characters > max.into());
+        if too_short == Some(true) || too_long == Some(true) {
+            self.error(
+                path,
+                ValidationErrorKind::Characters,
+                format!(
+                    "{} has {} characters, which is outside of {}..{}",
+                    path,
+                    characters,
+                    field.minlength.unwrap_or(0),
+                    field
+                        .maxlength
+                        .map(|max| max.to_string())
+                        .unwrap_or_default()
+                ),
+            );
+        }
+
+        let words = count_words(text);
+        let too_few = field.min_words.map(|min| words < min.into());
+        let too_many = field.max_words.map(|max| words > max.into());
+        if too_few == Some(true) || too_many == Some(true) {
+            self.error(
+                path,
+                ValidationErrorKind::Words,
+                format!(
+                    "{} has {} words, which is outside of {}..{}",
+                    path,
+                    words,
+                    field.min_words.unwrap_or(0),
+                    field
+                        .max_words
+                        .map(|max| max.to_string())
+                        .unwrap_or_default()
+                ),
+            );
+        }
+
+        #[cfg(feature = "regex")]
+        if let Some(ref pattern) = field.pattern {
+            // like the HTML attribute, the pattern has to match the whole value;
+            // patterns the regex crate can't parse, e.g. with look-ahead, are
+            // left to the browser, and reported by `Form::validate`
+            let matches = regex::Regex::new(&format!("^(?:{})$", pattern))
+                .map(|regex| regex.is_match(text))
+                .unwrap_or(true);
+            if !matches {
+                self.error(
+                    path,
+                    ValidationErrorKind::Pattern,
+                    format!("{} does not match the pattern {}", path, pattern),
+                );
+            }
+        }
+    }
+
+    fn check_repeat(&mut self, group: &FormGroup, optional: bool, scope: &Scope) {
+        let entries = match scope.data.get(&group.name) {
+            None | Some(Value::Null) => &[][..],
+            Some(Value::Array(entries)) => &entries[..],
+            Some(_) => {
+                return self.error(
+                    &group.name,
+                    ValidationErrorKind::NotAnObject,
+                    format!("{} should be a list of repetitions", group.name),
+                )
+            }
+        };
+
+        let min = group.min_repeat.unwrap_or(if optional { 0 } else { 1 });
+        let too_few = entries.len() < min.into();
+        let too_many = group.max_repeat.map(|max| entries.len() > max.into()) == Some(true);
+        if too_few || too_many {
+            self.error(
+                &group.name,
+                ValidationErrorKind::Repetitions,
+                format!(
+                    "{} is repeated {} times, which is outside of {}..{}",
+                    group.name,
+                    entries.len(),
+                    min,
+                    group
+                        .max_repeat
+                        .map(|max| max.to_string())
+                        .unwrap_or_default()
+                ),
+            );
+        }
+
+        for (i, entry) in entries.iter().enumerate() {
+            let prefix = format!("{}[{}]", group.name, i);
+            let entry = match entry.as_object() {
+                Some(entry) => entry,
+                None => {
+                    self.error(
+                        &prefix,
+                        ValidationErrorKind::NotAnObject,
+                        format!("{} should be an object", prefix),
+                    );
+                    continue;
+                }
+            };
+            for key in entry.keys() {
+                if !group.members.iter().any(|field| &field.name == key) {
+                    let path = format!("{}.{}", prefix, key);
+                    self.error(
+                        &path,
+                        ValidationErrorKind::Unknown,
+                        format!("{} is not a member of {}", key, group.name),
+                    );
+                }
+            }
+            let scope = Scope {
+                entry: Some(entry),
+                data: scope.data,
+            };
+            for field in &group.members {
+                if field.disabled || !scope.applies(&field.attributes) {
+                    continue;
+                }
+                // an optional group may be left out, but a repetition that is
+                // there has to be complete
+                let path = format!("{}.{}", prefix, field.name);
+                let optional = scope.is_optional(&field.attributes);
+                self.check_field(field, &path, optional, &scope);
+            }
+        }
+    }
+}
+
+impl Form {
+    /// Checks a submission: fields that apply and are not optional are
+    /// filled in, selected options exist, numbers are numbers, checkboxes are
+    /// booleans, and selections, word counts and repetitions are within
+    /// their limits.
+    ///
+    /// Keys that are not part of the form are reported as
+    /// [`ValidationErrorKind::Unknown`], so that callers can choose to ignore
+    /// them.
+    pub fn validate_submission(&self, data: &Value) -> Result<(), Vec<ValidationError>> {
+        let mut checker = Checker { errors: Vec::new() };
+        let data = match data.as_object() {
+            Some(data) => data,
+            None => {
+                checker.error(
+                    "",
+                    ValidationErrorKind::NotAnObject,
+                    String::from("a submission should be an object"),
+                );
+                return Err(checker.errors);
+            }
+        };
+
+        for key in data.keys() {
+            let known = self
+                .sections
+                .iter()
+                .flat_map(|section| &section.elements)
+                .any(|element| match element {
+                    FormElement::Field(field) => &field.name == key,
+                    FormElement::Group(group) if group.repeat => &group.name == key,
+                    FormElement::Group(group) => {
+                        group.members.iter().any(|field| &field.name == key)
+                    }
+                });
+            if !known {
+                checker.error(
+                    key,
+                    ValidationErrorKind::Unknown,
+                    format!("{} is not a field of this form", key),
+                );
+            }
+        }
+
+        let scope = Scope { entry: None, data };
+        for section in &self.sections {
+            if !scope.applies(&section.attributes) {
+                continue;
+            }
+            let section_optional = scope.is_optional(&section.attributes);
+            for element in &section.elements {
+                match element {
+                    FormElement::Field(field) => {
+                        if field.disabled || !scope.applies(&field.attributes) {
+                            continue;
+                        }
+                        let optional = section_optional || scope.is_optional(&field.attributes);
+                        checker.check_field(field, &field.name, optional, &scope);
+                    }
+                    FormElement::Group(group) => {
+                        if group.disabled || !scope.applies(&group.attributes) {
+                            continue;
+                        }
+                        let optional = section_optional || scope.is_optional(&group.attributes);
+                        if group.repeat {
+                            checker.check_repeat(group, optional, &scope);
+                            continue;
+                        }
+                        for field in &group.members {
+                            if field.disabled || !scope.applies(&field.attributes) {
+                                continue;
+                            }
+                            let optional = optional || scope.is_optional(&field.attributes);
+                            checker.check_field(field, &field.name, optional, &scope);
+                        }
+                    }
+                }
+            }
+        }
+
+        if checker.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(checker.errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn form() -> Form {
+        FormBuilder::new()
+            .section(
+                SectionBuilder::new("applicant")
+                    .field(FieldBuilder::new("surname", FieldType::Text))
+                    .field(FieldBuilder::new("age", FieldType::Number))
+                    .field(
+                        FieldBuilder::new("status", FieldType::Select)
+                            .option("single", "Single")
+                            .option("married", "Married"),
+                    )
+                    .field(FieldBuilder::new("spouse", FieldType::Text).requires("status.married"))
+                    .field(FieldBuilder::new("consent", FieldType::Checkbox).optional()),
+            )
+            .section(
+                SectionBuilder::new("family").group(
+                    GroupBuilder::new("child", GroupType::Subsection)
+                        .repeat()
+                        .max_repeat(2)
+                        .optional()
+                        .field(FieldBuilder::new("child-name", FieldType::Text)),
+                ),
+            )
+            .build()
+            .unwrap()
+    }
+
+    fn kinds(errors: Vec<ValidationError>) -> Vec<(String, ValidationErrorKind)> {
+        errors
+            .into_iter()
+            .map(|error| (error.field, error.kind))
+            .collect()
+    }
+
+    #[test]
+    fn valid_submission() {
+        let data = json!({
+            "surname": "Smith",
+            "age": 40,
+            "status": "married",
+            "spouse": "Jane Smith",
+            "child": [{ "child-name": "Ann" }],
+        });
+        form().validate_submission(&data).unwrap();
+
+        // the spouse does not apply to single applicants
+        let data = json!({ "surname": "Smith", "age": "40", "status": "single" });
+        form().validate_submission(&data).unwrap();
+    }
+
+    #[test]
+    fn invalid_submission() {
+        let data = json!({
+            "age": "forty",
+            "status": "divorced",
+            "consent": "yes",
+            "nickname": "Smithy",
+            "child": [{ "child-name": "Ann" }, {}, { "child-name": "Bo" }],
+        });
+        use ValidationErrorKind::*;
+        assert_eq!(
+            kinds(form().validate_submission(&data).unwrap_err()),
+            [
+                (String::from("nickname"), Unknown),
+                (String::from("surname"), Missing),
+                (String::from("age"), NotANumber),
+                (String::from("status"), NotAnOption),
+                (String::from("consent"), NotABoolean),
+                (String::from("child"), Repetitions),
+                (String::from("child[1].child-name"), Missing),
+            ]
+        );
+    }
+
+    #[test]
+    fn requires() {
+        let data = json!({ "surname": "Smith", "age": 40, "status": "married" });
+        let errors = form().validate_submission(&data).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, ValidationErrorKind::Missing);
+        assert_eq!(errors[0].to_string(), "spouse must be filled in");
+    }
+
+    #[test]
+    fn characters() {
+        let form = FormBuilder::new()
+            .section(
+                SectionBuilder::new("applicant").field(
+                    FieldBuilder::new("initials", FieldType::Text)
+                        .minlength(2)
+                        .maxlength(3),
+                ),
+            )
+            .build()
+            .unwrap();
+        form.validate_submission(&json!({ "initials": "JRR" }))
+            .unwrap();
+        for initials in &["J", "JRRT"] {
+            let errors = form
+                .validate_submission(&json!({ "initials": *initials }))
+                .unwrap_err();
+            assert_eq!(
+                kinds(errors),
+                [(String::from("initials"), ValidationErrorKind::Characters)]
+            );
+        }
+    }
+
+    #[test]
+    fn number_bounds() {
+        let form = FormBuilder::new()
+            .section(
+                SectionBuilder::new("applicant").field(
+                    FieldBuilder::new("age", FieldType::Number)
+                        .min(18.0)
+                        .max(120.0),
+                ),
+            )
+            .build()
+            .unwrap();
+        form.validate_submission(&json!({ "age": 40 })).unwrap();
+        for age in [json!(17), json!("121")] {
+            let errors = form
+                .validate_submission(&json!({ "age": age }))
+                .unwrap_err();
+            assert_eq!(
+                kinds(errors),
+                [(String::from("age"), ValidationErrorKind::OutOfRange)]
+            );
+        }
+    }
+
+    #[test]
+    fn not_an_object() {
+        let errors = form().validate_submission(&json!(["Smith"])).unwrap_err();
+        assert_eq!(
+            kinds(errors),
+            [(String::new(), ValidationErrorKind::NotAnObject)]
+        );
+    }
+}
@@ -0,0 +1,333 @@
+//! Translating forms outside their templates.
+//!
+//! Every translatable string gets a key made from the names of the elements
+//! it belongs to, like `applicant.surname.label`, so keys stay the same from
+//! one compile to the next as long as names don't change. Links, anonymous
+//! groups and optgroups, which have no names, are keyed by their position
+//! among the links of the form, the anonymous groups of their section or the
+//! optgroups of their field, counting from 0, like `links.0.label`,
+//! `applicant.group-0.title` and `applicant.country.optgroup.0`. The strings
+//! are the same ones [`Form::pseudolocalize`] rewrites.
+
+use super::*;
+
+/// A string to translate, as found by [`Form::extract_strings`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranslatableString {
+    pub key: String,
+    pub text: String,
+    /// Whether `text` is markup, i.e. instructions, whose tags and entities
+    /// should be kept as they are.
+    pub markup: bool,
+}
+
+/// Calls `visit` with the key of every string a form may have, set or not,
+/// and whether it is markup.
+fn visit_strings(form: &mut Form, visit: &mut dyn FnMut(String, bool, &mut Option<String>)) {
+    visit(String::from("title"), false, &mut form.title);
+    visit(String::from("description"), false, &mut form.description);
+    visit(
+        String::from("meta-description"),
+        false,
+        &mut form.meta_description,
+    );
+    visit(
+        String::from("dir-description"),
+        false,
+        &mut form.dir_description,
+    );
+    visit(String::from("instructions"), true, &mut form.instructions);
+    for (i, link) in form.links.iter_mut().enumerate() {
+        visit(format!("links.{}.label", i), false, &mut link.label);
+    }
+    if let Some(ref mut submit) = form.submit {
+        visit(String::from("submit.label"), false, &mut submit.label);
+    }
+    if let Some(ref mut contact) = form.contact {
+        visit(String::from("contact.hours"), false, &mut contact.hours);
+        visit(String::from("contact.note"), false, &mut contact.note);
+    }
+
+    for section in &mut form.sections {
+        let path = section.name.clone();
+        visit(format!("{}.title", path), false, &mut section.title);
+        visit(
+            format!("{}.instructions", path),
+            true,
+            &mut section.instructions,
+        );
+        let mut anonymous = 0;
+        for element in &mut section.elements {
+            match element {
+                FormElement::Field(field) => visit_field(&path, field, visit),
+                FormElement::Group(group) => {
+                    let path = if group.name.is_empty() {
+                        anonymous += 1;
+                        format!("{}.group-{}", path, anonymous - 1)
+                    } else {
+                        format!("{}.{}", path, group.name)
+                    };
+                    visit(format!("{}.title", path), false, &mut group.title);
+                    visit(format!("{}.label", path), false, &mut group.label);
+                    visit(
+                        format!("{}.instructions", path),
+                        true,
+                        &mut group.instructions,
+                    );
+                    for field in &mut group.members {
+                        visit_field(&path, field, visit);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn visit_field(
+    path: &str,
+    field: &mut FormField,
+    visit: &mut dyn FnMut(String, bool, &mut Option<String>),
+) {
+    let path = format!("{}.{}", path, field.name);
+    visit(format!("{}.label", path), false, &mut field.label);
+    visit(
+        format!("{}.placeholder", path),
+        false,
+        &mut field.placeholder,
+    );
+    visit(
+        format!("{}.instructions", path),
+        true,
+        &mut field.instructions,
+    );
+    // each option holds the label of its optgroup, so the label is visited
+    // once per run of options and copied back to all of them
+    let mut start = 0;
+    let mut optgroups = 0;
+    while start < field.options.len() {
+        let mut optgroup = field.options[start].optgroup.clone();
+        let end = start
+            + field.options[start..]
+                .iter()
+                .take_while(|option| option.optgroup == optgroup)
+                .count();
+        if optgroup.is_some() {
+            visit(
+                format!("{}.optgroup.{}", path, optgroups),
+                false,
+                &mut optgroup,
+            );
+            optgroups += 1;
+        }
+        for option in &mut field.options[start..end] {
+            option.optgroup = optgroup.clone();
+            visit(
+                format!("{}.option.{}", path, option.name),
+                false,
+                &mut option.label,
+            );
+        }
+        start = end;
+    }
+}
+
+/// Escapes a string for a PO file, including its quotes.
+fn po_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// A gettext catalog of `strings` with empty translations, to be filled in
+/// by translators. Keys are written as `msgctxt`, so the same text in two
+/// places can be translated differently.
+pub fn to_po(strings: &[TranslatableString]) -> String {
+    let mut po =
+        String::from("msgid \"\"\nmsgstr \"Content-Type: text/plain; charset=UTF-8\\n\"\n");
+    for string in strings {
+        po.push('\n');
+        if string.markup {
+            po.push_str("#. markup: keep tags and entities as they are\n");
+        }
+        po.push_str("msgctxt ");
+        po_string(&string.key, &mut po);
+        po.push_str("\nmsgid ");
+        po_string(&string.text, &mut po);
+        po.push_str("\nmsgstr \"\"\n");
+    }
+    po
+}
+
+impl Form {
+    /// Every string that is set and should be translated, in document order.
+    /// See the [module documentation](self) for how they are keyed.
+    pub fn extract_strings(&self) -> Vec<TranslatableString> {
+        let mut strings = Vec::new();
+        visit_strings(&mut self.clone(), &mut |key, markup, text| {
+            if let Some(text) = text {
+                strings.push(TranslatableString {
+                    key,
+                    text: text.clone(),
+                    markup,
+                });
+            }
+        });
+        strings
+    }
+
+    /// Replaces the strings whose keys are in `translations`. Keys that
+    /// aren't in `translations`, or map to an empty string, i.e. haven't been
+    /// translated, are left as they are.
+    pub fn apply_translations(&mut self, translations: &HashMap<String, String>) {
+        visit_strings(self, &mut |key, _, text| {
+            if let Some(translation) = translations.get(&key).filter(|t| !t.is_empty()) {
+                *text = Some(translation.clone());
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_and_apply() {
+        let form = compile("resources/translation.pug").unwrap();
+        let strings = form.extract_strings();
+        let keys: Vec<&str> = strings.iter().map(|s| s.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            [
+                "title",
+                "order.title",
+                "order.dish.label",
+                "order.dish.option.fish",
+                "order.dish.option.chips",
+                "order.group-0.title",
+                "order.group-0.extra.label",
+                "order.group-0.extra.placeholder",
+                "order.notes.label",
+                "order.notes.instructions",
+                "order.group-1.title",
+                "order.group-1.address.label",
+            ]
+        );
+        assert!(strings[9].markup);
+        assert_eq!(
+            compile("resources/translation.pug")
+                .unwrap()
+                .extract_strings(),
+            strings
+        );
+
+        let translations: HashMap<String, String> = strings
+            .iter()
+            .map(|s| (s.key.clone(), format!("[{}]", s.text)))
+            .chain(std::iter::once((
+                String::from("order.title"),
+                String::new(),
+            )))
+            .collect();
+        let mut translated = form.clone();
+        translated.apply_translations(&translations);
+        assert_eq!(translated.title(), Some("[Order]"));
+        assert_eq!(translated.sections()[0].title(), Some("Your order"));
+        let dish = translated.find_field("dish").unwrap();
+        assert_eq!(dish.label(), Some("[Dish]"));
+        assert_eq!(dish.options()[1].label(), Some("[Chips]"));
+        assert_eq!(
+            translated.find_field("extra").unwrap().placeholder(),
+            Some("[e.g. \"vinegar\"]")
+        );
+        assert_eq!(
+            translated
+                .find_field("notes")
+                .unwrap()
+                .instructions
+                .as_deref(),
+            Some("[Tell us about <b>allergies</b>.]")
+        );
+        assert!(translated.structurally_eq(&form));
+    }
+
+    #[test]
+    fn optgroups() {
+        let form = compile_xml_str(
+            "<form><section name='a'><field name='country' type='select'>Country\
+                <option name='none'>None</option>\
+                <optgroup label='Europe'><option name='fr'>France</option><option name='de'>Germany</option></optgroup>\
+                <optgroup label='Asia'><option name='jp'>Japan</option></optgroup>\
+            </field></section></form>",
+        )
+        .unwrap();
+        let keys: Vec<_> = form
+            .extract_strings()
+            .into_iter()
+            .map(|s| (s.key, s.text))
+            .collect();
+        let key = |key: &str, text: &str| (String::from(key), String::from(text));
+        assert_eq!(
+            keys,
+            [
+                key("a.country.label", "Country"),
+                key("a.country.option.none", "None"),
+                key("a.country.optgroup.0", "Europe"),
+                key("a.country.option.fr", "France"),
+                key("a.country.option.de", "Germany"),
+                key("a.country.optgroup.1", "Asia"),
+                key("a.country.option.jp", "Japan"),
+            ]
+        );
+
+        let mut translated = form.clone();
+        let translations = [(String::from("a.country.optgroup.0"), String::from("Europa"))];
+        translated.apply_translations(&translations.iter().cloned().collect());
+        let groups: Vec<_> = translated
+            .find_field("country")
+            .unwrap()
+            .option_groups()
+            .iter()
+            .map(|group| (group.label, group.options.len()))
+            .collect();
+        assert_eq!(groups, [(None, 1), (Some("Europa"), 2), (Some("Asia"), 1)]);
+    }
+
+    #[test]
+    fn po() {
+        let strings = [
+            TranslatableString {
+                key: String::from("order.extra.placeholder"),
+                text: String::from("e.g. \"vinegar\""),
+                markup: false,
+            },
+            TranslatableString {
+                key: String::from("order.notes.instructions"),
+                text: String::from("Tell us about <b>allergies</b>."),
+                markup: true,
+            },
+        ];
+        assert_eq!(
+            to_po(&strings),
+            "msgid \"\"\n\
+             msgstr \"Content-Type: text/plain; charset=UTF-8\\n\"\n\
+             \n\
+             msgctxt \"order.extra.placeholder\"\n\
+             msgid \"e.g. \\\"vinegar\\\"\"\n\
+             msgstr \"\"\n\
+             \n\
+             #. markup: keep tags and entities as they are\n\
+             msgctxt \"order.notes.instructions\"\n\
+             msgid \"Tell us about <b>allergies</b>.\"\n\
+             msgstr \"\"\n"
+        );
+    }
+}
@@ -0,0 +1,239 @@
+//! TypeScript interfaces for submissions.
+//!
+//! By default the interface has the shape [`Form::validate_submission`]
+//! expects: a property per field, with the members of repeatable groups in an
+//! array under the group's name. That only holds while field names are unique,
+//! which compiling warns about and [`Form::validate`] checks; fields sharing a
+//! name give repeated properties. [`TypeScriptOptions::nest_groups`] gives a
+//! different shape, which `validate_submission` does not accept.
+//!
+//! Fields are optional (`?`) unless they are always asked for, i.e. they,
+//! their group and their section are neither optional nor conditional, and
+//! they are not disabled. Files, images and grids are not part of the JSON
+//! submission and are typed as `unknown`.
+
+use super::*;
+
+/// How [`Form::to_typescript_with_options`] lays out groups.
+#[derive(Debug, Clone, Default)]
+pub struct TypeScriptOptions {
+    nest_groups: bool,
+}
+
+impl TypeScriptOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Type the members of a group as an object property named after the
+    /// group, rather than as properties of their own. Repeatable groups are
+    /// always an array of objects. This is not the shape
+    /// [`Form::validate_submission`] expects, since it only nests repeatable
+    /// groups; it is for pages that submit groups as objects themselves.
+    pub fn nest_groups(mut self) -> Self {
+        self.nest_groups = true;
+        self
+    }
+}
+
+/// Whether `name` can be used as a property name without quotes.
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '$' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        }
+        _ => false,
+    }
+}
+
+fn string_literal(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn property_name(name: &str, out: &mut String) {
+    if is_identifier(name) {
+        out.push_str(name);
+    } else {
+        string_literal(name, out);
+    }
+}
+
+/// The type of a field's value in a submission.
+fn value_type(field: &FormField) -> String {
+    match field.field_type {
+        FieldType::Number | FieldType::Range => String::from("number"),
+        FieldType::Checkbox => String::from("boolean"),
+        FieldType::MultiSelect => String::from("string[]"),
+        FieldType::Select if !field.options.is_empty() => {
+            let mut union = String::new();
+            for (i, option) in field.options.iter().enumerate() {
+                if i > 0 {
+                    union.push_str(" | ");
+                }
+                string_literal(&option.name, &mut union);
+            }
+            union
+        }
+        FieldType::File | FieldType::Image | FieldType::Grid => String::from("unknown"),
+        _ => String::from("string"),
+    }
+}
+
+/// Writes the properties of an interface or object type, one per line.
+struct Properties {
+    out: String,
+    depth: usize,
+}
+
+impl Properties {
+    fn property(&mut self, name: &str, optional: bool, value_type: &str) {
+        for _ in 0..self.depth {
+            self.out.push_str("  ");
+        }
+        property_name(name, &mut self.out);
+        if optional {
+            self.out.push('?');
+        }
+        self.out.push_str(": ");
+        self.out.push_str(value_type);
+        self.out.push_str(";\n");
+    }
+
+    fn field(&mut self, field: &FormField, optional: bool) {
+        let optional = optional || field.disabled || !field.attributes.is_always_required();
+        self.property(&field.name, optional, &value_type(field));
+    }
+
+    /// Starts an object type property, which is ended by `end`.
+    fn start(&mut self, name: &str, optional: bool) {
+        for _ in 0..self.depth {
+            self.out.push_str("  ");
+        }
+        property_name(name, &mut self.out);
+        if optional {
+            self.out.push('?');
+        }
+        self.out.push_str(": {\n");
+        self.depth += 1;
+    }
+
+    fn end(&mut self, suffix: &str) {
+        self.depth -= 1;
+        for _ in 0..self.depth {
+            self.out.push_str("  ");
+        }
+        self.out.push('}');
+        self.out.push_str(suffix);
+        self.out.push_str(";\n");
+    }
+}
+
+impl Form {
+    /// An exported interface named `interface_name` for the form's
+    /// submissions, with the members of groups as properties of their own.
+    pub fn to_typescript(&self, interface_name: &str) -> String {
+        self.to_typescript_with_options(interface_name, &TypeScriptOptions::new())
+    }
+
+    pub fn to_typescript_with_options(
+        &self,
+        interface_name: &str,
+        options: &TypeScriptOptions,
+    ) -> String {
+        let mut properties = Properties {
+            out: format!("export interface {} {{\n", interface_name),
+            depth: 1,
+        };
+        for section in &self.sections {
+            let section_optional = !section.attributes.is_always_required();
+            for element in &section.elements {
+                match element {
+                    FormElement::Field(field) => properties.field(field, section_optional),
+                    FormElement::Group(group) => {
+                        let optional = section_optional
+                            || group.disabled
+                            || !group.attributes.is_always_required()
+                            || group.min_repeat == Some(0);
+                        if group.repeat || options.nest_groups {
+                            properties.start(&group.name, optional);
+                            for field in &group.members {
+                                properties.field(field, false);
+                            }
+                            properties.end(if group.repeat { "[]" } else { "" });
+                        } else {
+                            for field in &group.members {
+                                properties.field(field, optional);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        properties.out.push_str("}\n");
+        properties.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_field_type() {
+        let form = compile("resources/field-types.pug").unwrap();
+        assert_eq!(
+            form.to_typescript("Submission"),
+            std::fs::read_to_string("resources/field-types.ts").unwrap()
+        );
+    }
+
+    #[test]
+    fn groups() {
+        let form = compile_xml_str(
+            r#"<form>
+                <section name="applicant">
+                    <group name="name" type="row">
+                        <field name="given_name" type="text">Given name</field>
+                        <field name="surname" type="text" optional="">Surname</field>
+                    </group>
+                    <group name="children" type="subsection" repeat="">
+                        <field name="child-name" type="text">Name</field>
+                    </group>
+                </section>
+            </form>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            form.to_typescript("Applicant"),
+            "export interface Applicant {\n  \
+               given_name: string;\n  \
+               surname?: string;\n  \
+               children: {\n    \
+                 \"child-name\": string;\n  \
+               }[];\n\
+             }\n"
+        );
+        assert_eq!(
+            form.to_typescript_with_options("Applicant", &TypeScriptOptions::new().nest_groups()),
+            "export interface Applicant {\n  \
+               name: {\n    \
+                 given_name: string;\n    \
+                 surname?: string;\n  \
+               };\n  \
+               children: {\n    \
+                 \"child-name\": string;\n  \
+               }[];\n\
+             }\n"
+        );
+    }
+}
@@ -0,0 +1,87 @@
+//! Walking a form's sections, groups and fields in document order, like a
+//! stream of XML events, e.g. to drive a renderer.
+
+use super::*;
+use std::iter;
+
+/// A step of [`Form::walk`]. Every start is matched by an end, and fields
+/// come between the start and end of their group or section.
+#[derive(Debug, Clone, Copy)]
+pub enum Node<'a> {
+    SectionStart(&'a FormSection),
+    GroupStart(&'a FormGroup),
+    Field(&'a FormField),
+    GroupEnd(&'a FormGroup),
+    SectionEnd(&'a FormSection),
+}
+
+impl Form {
+    pub fn walk(&self) -> impl Iterator<Item = Node<'_>> {
+        self.sections.iter().flat_map(|section| {
+            let elements = section.elements.iter().flat_map(|element| {
+                let (start, fields, end) = match element {
+                    FormElement::Field(field) => (None, std::slice::from_ref(field), None),
+                    FormElement::Group(group) => (
+                        Some(Node::GroupStart(group)),
+                        &group.members[..],
+                        Some(Node::GroupEnd(group)),
+                    ),
+                };
+                start
+                    .into_iter()
+                    .chain(fields.iter().map(Node::Field))
+                    .chain(end)
+            });
+            iter::once(Node::SectionStart(section))
+                .chain(elements)
+                .chain(iter::once(Node::SectionEnd(section)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn document_order() {
+        let form = FormBuilder::new()
+            .section(
+                SectionBuilder::new("applicant")
+                    .field(FieldBuilder::new("surname", FieldType::Text))
+                    .group(
+                        GroupBuilder::new("address", GroupType::Subsection)
+                            .field(FieldBuilder::new("city", FieldType::Text))
+                            .field(FieldBuilder::new("zip-code", FieldType::Text)),
+                    ),
+            )
+            .section(SectionBuilder::new("notes"))
+            .build()
+            .unwrap();
+
+        let nodes: Vec<String> = form
+            .walk()
+            .map(|node| match node {
+                Node::SectionStart(section) => format!("<{}>", section.name()),
+                Node::GroupStart(group) => format!("<{}>", group.name()),
+                Node::Field(field) => String::from(field.name()),
+                Node::GroupEnd(group) => format!("</{}>", group.name()),
+                Node::SectionEnd(section) => format!("</{}>", section.name()),
+            })
+            .collect();
+        assert_eq!(
+            nodes,
+            [
+                "<applicant>",
+                "surname",
+                "<address>",
+                "city",
+                "zip-code",
+                "</address>",
+                "</applicant>",
+                "<notes>",
+                "</notes>",
+            ]
+        );
+    }
+}